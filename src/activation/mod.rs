@@ -0,0 +1,65 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+use serde::{Deserialize, Serialize};
+
+/// The function applied to the weighted input of every node to get
+/// its output.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Activation
+{
+	/// The logistic function `1 / (1 + e^-x)`, squashing into `(0, 1)`.
+	#[default]
+	Sigmoid,
+	/// The hyperbolic tangent, squashing into `(-1, 1)`.
+	Tanh,
+	/// The rectified linear unit `max(0, x)`.
+	Relu,
+}
+
+impl Activation
+{
+	/// Applies the activation function to `x`.
+	pub(crate) fn apply(self, x: f64) -> f64
+	{
+		match self {
+			Activation::Sigmoid => 1_f64 / (1_f64 + (-x).exp()),
+			Activation::Tanh => x.tanh(),
+			Activation::Relu => x.max(0_f64),
+		}
+	}
+
+	/// Derivative of the activation function, expressed in terms of its
+	/// output `y`, which is what backpropagation has at hand.
+	pub(crate) fn derivative(self, y: f64) -> f64
+	{
+		match self {
+			Activation::Sigmoid => y * (1_f64 - y),
+			Activation::Tanh => 1_f64 - y * y,
+			Activation::Relu => if y > 0_f64 { 1_f64 } else { 0_f64 },
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::Activation;
+
+	#[test]
+	fn apply()
+	{
+		assert_eq!(Activation::Sigmoid.apply(0.), 0.5);
+		assert_eq!(Activation::Tanh.apply(0.5), 0.5_f64.tanh());
+		assert_eq!(Activation::Relu.apply(-2.), 0.);
+		assert_eq!(Activation::Relu.apply(2.), 2.);
+	}
+
+	#[test]
+	fn derivative()
+	{
+		assert_eq!(Activation::Sigmoid.derivative(0.5), 0.25);
+		assert_eq!(Activation::Tanh.derivative(0.), 1.);
+		assert_eq!(Activation::Relu.derivative(0.), 0.);
+		assert_eq!(Activation::Relu.derivative(3.), 1.);
+	}
+}
@@ -0,0 +1,49 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// How the weights of a new net are drawn. Every strategy draws
+/// uniformly from `[-s, s]`, only the scale `s` differs.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Init
+{
+	/// A fixed scale of `1`, independent of the layer size.
+	#[default]
+	Uniform,
+	/// Xavier/Glorot initialization, scaled by `1 / sqrt(fan_in)`. Works
+	/// well with sigmoid and tanh.
+	Xavier,
+	/// He initialization, scaled by `sqrt(2 / fan_in)`. Works well with
+	/// ReLU.
+	He,
+}
+
+impl Init
+{
+	/// Returns the scale for a layer with `fan_in` inputs, i.e. the
+	/// number of columns of its weight matrix. A `fan_in` of `0` is
+	/// treated like `1`.
+	pub(crate) fn scale(self, fan_in: u32) -> f64
+	{
+		let fan_in = fan_in.max(1) as f64;
+		match self {
+			Init::Uniform => 1_f64,
+			Init::Xavier => 1_f64 / fan_in.sqrt(),
+			Init::He => (2_f64 / fan_in).sqrt(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::Init;
+
+	#[test]
+	fn scale()
+	{
+		assert_eq!(Init::Uniform.scale(100), 1.);
+		assert_eq!(Init::Xavier.scale(4), 0.5);
+		assert_eq!(Init::He.scale(8), 0.5);
+		assert_eq!(Init::Xavier.scale(0), 1.);
+	}
+}
@@ -6,8 +6,12 @@
 //! power of WebAssembly, it's very fast compared to a pure JS
 //! implementation.
 
+mod activation;
+mod init;
 mod matrix;
 mod neural_net;
 
-pub use matrix::Matrix;
-pub use neural_net::NeuralNet;
+pub use activation::Activation;
+pub use init::Init;
+pub use matrix::{Matrix, MatrixError};
+pub use neural_net::{NeuralNet, NeuralNetSnapshot};
@@ -1,10 +1,13 @@
 use wasm_bindgen::prelude::{JsValue, wasm_bindgen };
-use std::convert::TryInto;
+use rand::{Rng, SeedableRng, rngs::StdRng, thread_rng};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use crate::Activation;
 
 /// A matrix is like a table of `f64` numbers. Each item has a position
 /// and value.
 #[wasm_bindgen]
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Matrix
 {
 	rows: u32,
@@ -12,6 +15,36 @@ pub struct Matrix
 	data: Vec<f64>,
 }
 
+/// Errors of the matrix methods that are **not** accessable from
+/// JavaScript. Unlike a `JsValue` they can be handled on every target;
+/// the JavaScript methods convert them with `JsValue::from`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatrixError
+{
+	/// Two matrices that must have the same dimensions don't.
+	DimensionMismatch,
+}
+
+impl fmt::Display for MatrixError
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		match self {
+			MatrixError::DimensionMismatch => write!(f, "Error: dimensions of left-hand-side must match right-hand-side"),
+		}
+	}
+}
+
+impl std::error::Error for MatrixError {}
+
+impl From<MatrixError> for JsValue
+{
+	fn from(err: MatrixError) -> JsValue
+	{
+		JsValue::from_str(&err.to_string())
+	}
+}
+
 /// Methods in this `impl` are shared and accessable from JavaScript.
 #[wasm_bindgen]
 impl Matrix
@@ -39,7 +72,7 @@ impl Matrix
 	/// ```
 	pub fn from(rows: u32, cols: u32, list: Vec<f64>) -> Result<Matrix, JsValue>
 	{
-		if rows * cols != list.len().try_into().unwrap() {
+		if (rows * cols) as usize != list.len() {
 			return Err(JsValue::from_str("Length of list does not match `rows` x `cols`"))
 		}
 
@@ -91,6 +124,9 @@ impl Matrix
 	}
 
 	/// Returns the value at a row & column position of the matrix.
+	/// The position is not checked: an out-of-range `col` silently
+	/// reads from another row, and an out-of-range `row` panics. Use
+	/// `try_get` to get an error instead.
 	/// ```
 	/// let mut m = neural_net_rs::Matrix::new(2, 2);
 	/// let m_cols = m.cols();
@@ -104,7 +140,38 @@ impl Matrix
 	pub fn get(&self, row: u32, col: u32) -> f64
 	{
 		let idx = self.calc_idx(row, col);
-		*(&self.data[idx])
+		self.data[idx]
+	}
+
+	/// Returns the value at a row & column position of the matrix.
+	/// Fails if `row` or `col` is out of range.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 2, vec![1., 2.]).unwrap();
+	/// assert_eq!(m.try_get(0, 1).unwrap(), 2.);
+	/// ```
+	pub fn try_get(&self, row: u32, col: u32) -> Result<f64, JsValue>
+	{
+		if row >= self.rows || col >= self.cols {
+			return Err(JsValue::from_str(&format!(
+				"Error: position ({}, {}) is out of range for a {}x{} matrix",
+				row, col, self.rows, self.cols
+			)));
+		}
+		Ok(self.get(row, col))
+	}
+
+	/// Sets the value at a row & column position of the matrix.
+	/// ```
+	/// let mut m = neural_net_rs::Matrix::new(2, 2);
+	/// m.set(1, 1, 9.);
+	/// assert_eq!(m.get(1, 1), 9.);
+	/// assert_eq!(m.data(), vec![0., 0., 0., 9.]);
+	/// ```
+	pub fn set(&mut self, row: u32, col: u32, value: f64)
+	{
+		let idx = self.calc_idx(row, col);
+		self.data[idx] = value;
 	}
 
 	/// Scale every item in the matrix by some float.
@@ -129,6 +196,72 @@ impl Matrix
 		self.map(|val, _, _| val * num);
 	}
 
+	/// Overwrites every item with a random value in `[-1, 1]`.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let mut m = Matrix::new(2, 2);
+	/// m.randomize();
+	/// assert!(m.data().iter().all(|v| *v >= -1. && *v <= 1.));
+	/// ```
+	pub fn randomize(&mut self)
+	{
+		let mut rng = thread_rng();
+		self.map(|_, _, _| rng.gen_range(-1_f64..=1_f64));
+	}
+
+	/// Returns a new matrix filled with random values in `[-1, 1]`,
+	/// drawn from a RNG seeded with `seed`. The same seed and dimensions
+	/// always give the same matrix, which makes it handy for tests.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let a = Matrix::random(2, 3, 42);
+	/// assert_eq!(a.data(), Matrix::random(2, 3, 42).data());
+	/// ```
+	pub fn random(rows: u32, cols: u32, seed: u64) -> Matrix
+	{
+		let mut rng = StdRng::seed_from_u64(seed);
+		let mut m = Matrix::new(rows, cols);
+		m.map(|_, _, _| rng.gen_range(-1_f64..=1_f64));
+		m
+	}
+
+	/// Returns an `n`x`n` identity matrix with `1` on the diagonal and
+	/// `0` everywhere else. `Matrix::mult(&Matrix::identity(n), &m)`
+	/// returns `m` unchanged for every `m` with `n` rows.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 3, vec![1., 2., 3., 4., 5., 6.]).unwrap();
+	/// let product = Matrix::mult(&Matrix::identity(2), &m).unwrap();
+	/// assert_eq!(product.data(), m.data());
+	/// ```
+	pub fn identity(n: u32) -> Matrix
+	{
+		let mut m = Matrix::new(n, n);
+		m.map(|_, r, c| if r == c { 1_f64 } else { 0_f64 });
+		m
+	}
+
+	/// Returns a new matrix with rows and columns swapped, so that
+	/// `t.get(c, r) == m.get(r, c)`. The original is not changed.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 3, vec![1., 2., 3., 4., 5., 6.]).unwrap();
+	/// let t = m.transpose();
+	/// // |1, 2, 3|      |1, 4|
+	/// // |4, 5, 6|  ->  |2, 5|
+	/// //                |3, 6|
+	/// assert_eq!(t.rows(), 3);
+	/// assert_eq!(t.cols(), 2);
+	/// assert_eq!(t.data(), vec![1., 4., 2., 5., 3., 6.]);
+	/// assert_eq!(m.data(), vec![1., 2., 3., 4., 5., 6.]);
+	/// ```
+	pub fn transpose(&self) -> Matrix
+	{
+		let mut result = Matrix::new(self.cols, self.rows);
+		result.map(|_, row, col| self.get(col, row));
+		result
+	}
+
 	/// Converts a 2D position in the matrix into a index to
 	/// look up in the data array.
 	fn calc_idx(&self, i: u32, j: u32) -> usize
@@ -161,110 +294,2367 @@ impl Matrix
 		result.map(|_, row, col| {
 			let mut sum = 0_f64;
 			for k in 0..a.cols() {
-				sum = sum + a.get(row, k) * b.get(k, col);
+				sum += a.get(row, k) * b.get(k, col);
 			}
 			sum
 		});
 		
 		Ok(result)
 	}
-}
 
-/// Methods in this `impl` are **not** accessable from JavaScript.
-impl Matrix
-{
-	/// Maps over each position in the matrix (starting top-left
-	/// going left to right). A callback is called on each item
-	/// of the matrix with the current `value`, its `row` and
-	/// its `column`.
-	pub fn map<F>(&mut self, cb: F) -> ()
-	where F: Fn(f64, u32, u32) -> f64
+	/// Element-wise sum of two matrices -> returns a new Matrix.
+	/// Could fail because both matrices must have the same dimensions.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let a = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+	/// let b = Matrix::from(2, 2, vec![10., 20., 30., 40.]).unwrap();
+	/// assert_eq!(Matrix::add(&a, &b).unwrap().data(), vec![11., 22., 33., 44.]);
+	/// ```
+	pub fn add(a: &Matrix, b: &Matrix) -> Result<Matrix, JsValue>
 	{
-		for i in 0..self.rows {
-			for j in 0..self.cols {
-				let idx = self.calc_idx(i, j);
-				self.data[idx] = cb(self.data[idx], i, j);
+		Matrix::zip_map(a, b, |x, y| x + y).map_err(JsValue::from)
+	}
+
+	/// Element-wise difference `a - b` of two matrices -> returns a new
+	/// Matrix. Could fail because both matrices must have the same
+	/// dimensions.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let a = Matrix::from(2, 2, vec![10., 20., 30., 40.]).unwrap();
+	/// let b = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+	/// assert_eq!(Matrix::sub(&a, &b).unwrap().data(), vec![9., 18., 27., 36.]);
+	/// ```
+	pub fn sub(a: &Matrix, b: &Matrix) -> Result<Matrix, JsValue>
+	{
+		Matrix::zip_map(a, b, |x, y| x - y).map_err(JsValue::from)
+	}
+
+	/// Hadamard product of two matrices -> returns a new Matrix where
+	/// each item is `a.get(r, c) * b.get(r, c)`. This is **not** the
+	/// matrix product, see `mult` for that. Could fail because both
+	/// matrices must have the same dimensions.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let a = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+	/// let b = Matrix::from(2, 2, vec![5., 6., 7., 8.]).unwrap();
+	/// assert_eq!(Matrix::hadamard(&a, &b).unwrap().data(), vec![5., 12., 21., 32.]);
+	/// // whereas `mult` computes row-by-column sums:
+	/// assert_eq!(Matrix::mult(&a, &b).unwrap().data(), vec![19., 22., 43., 50.]);
+	/// ```
+	pub fn hadamard(a: &Matrix, b: &Matrix) -> Result<Matrix, JsValue>
+	{
+		Matrix::zip_map(a, b, |x, y| x * y).map_err(JsValue::from)
+	}
+
+	/// Frobenius distance between two matrices, i.e. the square root of
+	/// the summed squared differences of all items. Could fail because
+	/// both matrices must have the same dimensions.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let a = Matrix::from(1, 2, vec![1., 2.]).unwrap();
+	/// let b = Matrix::from(1, 2, vec![4., 6.]).unwrap();
+	/// assert_eq!(Matrix::frobenius_distance(&a, &b).unwrap(), 5.);
+	/// ```
+	pub fn frobenius_distance(a: &Matrix, b: &Matrix) -> Result<f64, JsValue>
+	{
+		let diff = Matrix::sub(a, b)?;
+		Ok(diff.data.iter().map(|val| val * val).sum::<f64>().sqrt())
+	}
+
+	/// Checks whether two matrices have the same dimensions and every
+	/// pair of items differs by at most `epsilon`. Useful to compare
+	/// results of float-heavy operations, where exact equality rarely
+	/// holds.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let a = Matrix::from(1, 2, vec![0.1 + 0.2, 1.]).unwrap();
+	/// let b = Matrix::from(1, 2, vec![0.3, 1.]).unwrap();
+	/// assert!(Matrix::approx_eq(&a, &b, 1e-12));
+	/// ```
+	pub fn approx_eq(a: &Matrix, b: &Matrix, epsilon: f64) -> bool
+	{
+		a.rows == b.rows && a.cols == b.cols &&
+			a.data.iter().zip(b.data.iter()).all(|(x, y)| (x - y).abs() <= epsilon)
+	}
+
+	/// Matrix product of three matrices `a·b·c`. Picks whichever of
+	/// `(a·b)·c` and `a·(b·c)` needs fewer multiplications, which can
+	/// make a big difference for chains like a column vector times a
+	/// row vector times a matrix. Fails if the dimensions don't chain.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let a = Matrix::from(1, 2, vec![1., 2.]).unwrap();
+	/// let b = Matrix::from(2, 1, vec![3., 4.]).unwrap();
+	/// let c = Matrix::from(1, 1, vec![2.]).unwrap();
+	/// assert_eq!(Matrix::mult3(&a, &b, &c).unwrap().data(), vec![22.]);
+	/// ```
+	pub fn mult3(a: &Matrix, b: &Matrix, c: &Matrix) -> Result<Matrix, JsValue>
+	{
+		if a.cols != b.rows || b.cols != c.rows {
+			return Err(JsValue::from_str("Error: dimensions of the three matrices do not chain"));
+		}
+
+		let (left_first, right_first) = Matrix::mult3_costs(a, b, c);
+		if left_first <= right_first {
+			Matrix::mult(&Matrix::mult(a, b)?, c)
+		} else {
+			Matrix::mult(a, &Matrix::mult(b, c)?)
+		}
+	}
+
+	/// Returns the number of scalar multiplications needed for
+	/// `(a·b)·c` and `a·(b·c)` respectively.
+	fn mult3_costs(a: &Matrix, b: &Matrix, c: &Matrix) -> (u64, u64)
+	{
+		let (n, m, p, q) = (a.rows as u64, a.cols as u64, b.cols as u64, c.cols as u64);
+		(n * m * p + n * p * q, m * p * q + n * m * q)
+	}
+
+	/// Estimates the numerical rank of the matrix: the number of pivots
+	/// with an absolute value above `epsilon` found by Gaussian
+	/// elimination with partial pivoting.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![1., 2., 2., 4.]).unwrap();
+	/// assert_eq!(m.rank(1e-9), 1);
+	/// ```
+	pub fn rank(&self, epsilon: f64) -> u32
+	{
+		let mut m = self.clone();
+		let mut rank = 0;
+		for col in 0..self.cols {
+			if rank == self.rows {
+				break;
+			}
+
+			let pivot = (rank..self.rows)
+				.max_by(|a, b| m.get(*a, col).abs().total_cmp(&m.get(*b, col).abs()))
+				.unwrap();
+			if m.get(pivot, col).abs() <= epsilon {
+				continue;
+			}
+			for c in 0..self.cols {
+				let (a, b) = (m.calc_idx(pivot, c), m.calc_idx(rank, c));
+				m.data.swap(a, b);
+			}
+
+			for row in rank + 1..self.rows {
+				let factor = m.get(row, col) / m.get(rank, col);
+				for c in col..self.cols {
+					let idx = m.calc_idx(row, c);
+					m.data[idx] -= factor * m.get(rank, c);
+				}
 			}
+			rank += 1;
 		}
+		rank
 	}
-}
 
-#[cfg(test)]
-mod tests
-{
-	use super::Matrix;
+	/// Estimates the 2-norm condition number of the matrix, which is the
+	/// ratio of its largest to its smallest singular value. The singular
+	/// values are the square roots of the eigenvalues of `XᵀX`, or of
+	/// `XXᵀ` for a wide matrix, which has the same nonzero singular values
+	/// without the extra zero eigenvalues. A large value warns that the
+	/// matrix is close to singular. Matrices without full rank return
+	/// `f64::INFINITY`. Fails on an empty matrix.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![2., 0., 0., 1.]).unwrap();
+	/// assert!((m.condition_estimate().unwrap() - 2.).abs() < 1e-9);
+	/// ```
+	pub fn condition_estimate(&self) -> Result<f64, JsValue>
+	{
+		if self.rows == 0 || self.cols == 0 {
+			return Err(JsValue::from_str("Error: cannot estimate the condition of an empty matrix"));
+		}
 
-	#[test]
-	fn matrix_new()
+		let wide = self.rows < self.cols;
+		let size = if wide { self.rows } else { self.cols };
+		let mut gram = Matrix::new(size, size);
+		gram.map(|_, i, j| {
+			let mut sum = 0_f64;
+			if wide {
+				for k in 0..self.cols {
+					sum += self.get(i, k) * self.get(j, k);
+				}
+			} else {
+				for k in 0..self.rows {
+					sum += self.get(k, i) * self.get(k, j);
+				}
+			}
+			sum
+		});
+
+		let eigenvalues = gram.symmetric_eigenvalues();
+		let largest = eigenvalues.iter().cloned().fold(f64::MIN, f64::max);
+		let smallest = eigenvalues.iter().cloned().fold(f64::MAX, f64::min);
+
+		if smallest <= 0_f64 {
+			return Ok(f64::INFINITY);
+		}
+
+		Ok((largest / smallest).sqrt())
+	}
+
+	/// Returns the lower-triangular Cholesky factor `L` with `L·Lᵀ == self`.
+	/// Fails unless the matrix is square, symmetric and positive-definite.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![4., 2., 2., 2.]).unwrap();
+	/// assert_eq!(m.cholesky().unwrap().data(), vec![2., 0., 1., 1.]);
+	/// ```
+	pub fn cholesky(&self) -> Result<Matrix, JsValue>
 	{
-		let m = Matrix::new(3, 4);
-		assert_eq!(m.rows(), 3);
-		assert_eq!(m.cols(), 4);
-		assert_eq!(m.data(), vec![0_f64; 12]);
+		if self.rows != self.cols {
+			return Err(JsValue::from_str("Error: Cholesky decomposition requires a square matrix"));
+		}
+
+		let n = self.rows;
+		for i in 0..n {
+			for j in (i + 1)..n {
+				let (a, b) = (self.get(i, j), self.get(j, i));
+				if (a - b).abs() > 1e-12 * a.abs().max(b.abs()).max(1_f64) {
+					return Err(JsValue::from_str("Error: Cholesky decomposition requires a symmetric matrix"));
+				}
+			}
+		}
+
+		let mut lower = Matrix::new(n, n);
+		for j in 0..n {
+			let mut diagonal = self.get(j, j);
+			for k in 0..j {
+				diagonal -= lower.get(j, k).powi(2);
+			}
+			if diagonal <= 0_f64 {
+				return Err(JsValue::from_str("Error: Cholesky decomposition requires a positive-definite matrix"));
+			}
+			let l_jj = diagonal.sqrt();
+			let idx = lower.calc_idx(j, j);
+			lower.data[idx] = l_jj;
+
+			for i in (j + 1)..n {
+				let mut sum = self.get(i, j);
+				for k in 0..j {
+					sum -= lower.get(i, k) * lower.get(j, k);
+				}
+				let idx = lower.calc_idx(i, j);
+				lower.data[idx] = sum / l_jj;
+			}
+		}
+
+		Ok(lower)
 	}
 
-	#[test]
-	fn matrix_from()
+	/// Shuffles the columns of the matrix in place with a Fisher-Yates
+	/// shuffle. The same `seed` always results in the same permutation.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let mut a = Matrix::from(2, 3, vec![1., 2., 3., 4., 5., 6.]).unwrap();
+	/// let mut b = a.clone();
+	/// a.shuffle_cols(42);
+	/// b.shuffle_cols(42);
+	/// assert_eq!(a.data(), b.data());
+	/// ```
+	pub fn shuffle_cols(&mut self, seed: u64)
 	{
-		let v = vec![1., 2., 3., 4., 5., 6.];
-		let m1 = Matrix::from(2, 3, v.clone()).unwrap();
-		let m2 = Matrix::from(3, 2, v.clone()).unwrap();
-		assert_eq!(m1.rows(), 2);
-		assert_eq!(m1.cols(), 3);
-		assert_eq!(m1.data(), v);
-		assert_eq!(m2.data(), v);
+		let mut rng = StdRng::seed_from_u64(seed);
+
+		for i in (1..self.cols).rev() {
+			let j = rng.gen_range(0..=i);
+			for row in 0..self.rows {
+				let idx_i = self.calc_idx(row, i);
+				let idx_j = self.calc_idx(row, j);
+				self.data.swap(idx_i, idx_j);
+			}
+		}
 	}
 
-	#[test]
-	fn getters()
+	/// Estimates the dominant eigenvector of a square matrix with power
+	/// iteration. Starting from a random vector (seeded by `seed`), the
+	/// vector is repeatedly multiplied by the matrix and normalized.
+	/// Returns a `n`x`1` matrix of unit length. Fails if the matrix is not
+	/// square or the iteration collapses to the zero vector.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![3., 0., 0., 1.]).unwrap();
+	/// let v = m.power_iteration(50, 1).unwrap();
+	/// assert!((v.get(0, 0).abs() - 1.).abs() < 1e-9);
+	/// ```
+	pub fn power_iteration(&self, iterations: u32, seed: u64) -> Result<Matrix, JsValue>
 	{
-		let mut m = Matrix::new(2, 3);
-		m.map(|_, r, c| (r + c) as f64);
-		assert_eq!(m.rows(), 2); // |0, 1, 2|
-		assert_eq!(m.cols(), 3); // |1, 2, 3|
-		assert_eq!(m.data(), vec![0., 1., 2., 1., 2., 3.]);
-		assert_eq!(m.get(0, 1), 1.);
-		assert_eq!(m.get(1, 1), 2.);
+		if self.rows != self.cols {
+			return Err(JsValue::from_str("Error: power iteration requires a square matrix"));
+		}
+
+		let mut vector = Matrix::random(self.rows, 1, seed);
+
+		for _ in 0..iterations {
+			vector = Matrix::mult(self, &vector)?;
+			let norm = vector.data.iter().map(|v| v * v).sum::<f64>().sqrt();
+			if norm == 0_f64 {
+				return Err(JsValue::from_str("Error: power iteration collapsed to the zero vector"));
+			}
+			vector.scale(1_f64 / norm);
+		}
+
+		Ok(vector)
 	}
 
-	#[test]
-	fn scale()
+	/// Approximates the matrix exponential `e^A` with the first `terms`
+	/// terms of its Taylor series `I + A + A²/2! + A³/3! + ...`. More
+	/// terms are needed the larger the items of the matrix are. Fails if
+	/// the matrix is not square.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 1, vec![1.]).unwrap();
+	/// let e = m.exp_matrix(20).unwrap();
+	/// assert!((e.get(0, 0) - std::f64::consts::E).abs() < 1e-12);
+	/// ```
+	pub fn exp_matrix(&self, terms: u32) -> Result<Matrix, JsValue>
 	{
-		let mut m = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
-		m.scale(2.);
-		assert_eq!(m.data(), vec![2., 4., 6., 8.]);
+		if self.rows != self.cols {
+			return Err(JsValue::from_str("Error: the matrix exponential requires a square matrix"));
+		}
+
+		let mut result = Matrix::new(self.rows, self.cols);
+		let mut term = Matrix::identity(self.rows);
+		for k in 0..terms {
+			if k > 0 {
+				term = Matrix::mult(&term, self)?;
+				term.scale(1_f64 / k as f64);
+			}
+			result = Matrix::add(&result, &term)?;
+		}
+
+		Ok(result)
 	}
 
-	#[test]
-	fn calc_idx()
+	/// Smooths every row with a moving average over `window` elements.
+	/// Only fully covered windows are kept, so the result has
+	/// `cols - window + 1` columns. Fails if `window` is `0` or larger
+	/// than the number of columns.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 4, vec![1., 3., 5., 7.]).unwrap();
+	/// let avg = m.moving_average_rows(2).unwrap();
+	/// assert_eq!(avg.data(), vec![2., 4., 6.]);
+	/// ```
+	pub fn moving_average_rows(&self, window: u32) -> Result<Matrix, JsValue>
 	{
-		let m = Matrix::new(3, 4);
-		// |0, 1,  2,  3|
-		// |4, 5,  6,  7|
-		// |8, 9, 10, 12|
-		assert_eq!(m.calc_idx(0, 0), 0);
-		assert_eq!(m.calc_idx(0, 2), 2);
-		assert_eq!(m.calc_idx(1, 1), 5);
-		assert_eq!(m.calc_idx(2, 2), 10);
+		if window == 0 || window > self.cols {
+			return Err(JsValue::from_str("Error: window must be between 1 and the number of columns"));
+		}
+
+		let mut result = Matrix::new(self.rows, self.cols - window + 1);
+		result.map(|_, row, col| {
+			let mut sum = 0_f64;
+			for k in col..(col + window) {
+				sum += self.get(row, k);
+			}
+			sum / window as f64
+		});
+
+		Ok(result)
 	}
 
-	#[test]
-	fn mult()
+	/// Returns a copy of the matrix that only keeps a band around the
+	/// diagonal. Elements more than `lower` positions below or more than
+	/// `upper` positions above the diagonal are set to `0`. A bandwidth
+	/// of `u32::MAX` keeps that whole side of the matrix.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+	/// assert_eq!(m.band(0, 0).data(), vec![1., 0., 0., 4.]);
+	/// ```
+	pub fn band(&self, lower: u32, upper: u32) -> Matrix
 	{
-		let a = Matrix::from(2, 3, vec![5., 1., 2., 3., 2., 6.]).unwrap();
-		let b = Matrix::from(3, 2, vec![8., 7., 4., 4., 5., 1.]).unwrap();
+		let mut result = self.clone();
+		result.map(|val, row, col| {
+			if row > col.saturating_add(lower) || col > row.saturating_add(upper) {
+				0_f64
+			} else {
+				val
+			}
+		});
+		result
+	}
 
-		let c = Matrix::mult(&a, &b).unwrap();
-		assert_eq!(c.rows(), 2);
-		assert_eq!(c.cols(), 2);
-		assert_eq!(c.data(), vec![54., 41., 62., 35.]);
+	/// Clamps every item of the matrix into `[min, max]` and returns how
+	/// many items had to be changed. Useful to detect saturated values.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let mut m = Matrix::from(1, 3, vec![-2., 0.5, 3.]).unwrap();
+	/// assert_eq!(m.clamp_counting(0., 1.), 2);
+	/// assert_eq!(m.data(), vec![0., 0.5, 1.]);
+	/// ```
+	pub fn clamp_counting(&mut self, min: f64, max: f64) -> u32
+	{
+		let mut count = 0;
+		for val in self.data.iter_mut() {
+			let clamped = val.max(min).min(max);
+			if clamped != *val {
+				*val = clamped;
+				count += 1;
+			}
+		}
+		count
 	}
 
-	#[test]
-	fn map()
+	/// Counts the items of the matrix in `bins` equal-width buckets
+	/// spanning `[min, max]`. Each bucket includes its lower bound, the
+	/// last one also includes `max`. Items outside the range are counted
+	/// in the first or last bucket, `NaN` items are ignored. Returns an
+	/// empty vector if `bins` is `0`.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 4, vec![0.1, 0.4, 0.6, 0.9]).unwrap();
+	/// assert_eq!(m.histogram(2, 0., 1.), vec![2, 2]);
+	/// ```
+	pub fn histogram(&self, bins: u32, min: f64, max: f64) -> Vec<u32>
 	{
-		let mut m = Matrix::from(2, 3, vec![1., 2., 3., 4., 5., 6.]).unwrap();
-		m.map(|val, r, c| (val * (c * r) as f64));
-		assert_eq!(m.data(), vec![0., 0., 0., 0., 5., 12.]);
+		let mut counts = vec![0; bins as usize];
+		if bins == 0 {
+			return counts;
+		}
+
+		let width = (max - min) / bins as f64;
+		for val in self.data.iter().filter(|val| !val.is_nan()) {
+			let bin = ((val - min) / width).floor().max(0_f64).min((bins - 1) as f64);
+			counts[bin as usize] += 1;
+		}
+		counts
 	}
-}
\ No newline at end of file
+
+	/// Returns a new matrix holding the sign of every item: `-1` for
+	/// negative, `1` for positive and `0` for zero values.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 3, vec![-4., 0., 2.5]).unwrap();
+	/// assert_eq!(m.sign().data(), vec![-1., 0., 1.]);
+	/// ```
+	pub fn sign(&self) -> Matrix
+	{
+		let mut result = self.clone();
+		result.map(|val, _, _| {
+			if val > 0_f64 {
+				1_f64
+			} else if val < 0_f64 {
+				-1_f64
+			} else {
+				0_f64
+			}
+		});
+		result
+	}
+
+	/// Returns a new matrix with the natural logarithm of every item.
+	/// Negative items yield `NaN`, zero yields negative infinity.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 2, vec![1., 1_f64.exp()]).unwrap();
+	/// assert_eq!(m.ln().data(), vec![0., 1.]);
+	/// ```
+	pub fn ln(&self) -> Matrix
+	{
+		self.mapped(|val, _, _| val.ln())
+	}
+
+	/// Returns a new matrix with `e` raised to the power of every item.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 2, vec![0., 1.]).unwrap();
+	/// assert_eq!(m.exp().data(), vec![1., 1_f64.exp()]);
+	/// ```
+	pub fn exp(&self) -> Matrix
+	{
+		self.mapped(|val, _, _| val.exp())
+	}
+
+	/// Returns a new matrix with the square root of every item. Negative
+	/// items yield `NaN`.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 2, vec![4., 9.]).unwrap();
+	/// assert_eq!(m.sqrt().data(), vec![2., 3.]);
+	/// ```
+	pub fn sqrt(&self) -> Matrix
+	{
+		self.mapped(|val, _, _| val.sqrt())
+	}
+
+	/// Returns a new matrix with every item rounded to the nearest
+	/// integer. Halfway cases are rounded away from `0`.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 3, vec![1.4, 1.5, -1.5]).unwrap();
+	/// assert_eq!(m.round().data(), vec![1., 2., -2.]);
+	/// ```
+	pub fn round(&self) -> Matrix
+	{
+		self.mapped(|val, _, _| val.round())
+	}
+
+	/// Returns a new matrix with every item rounded down.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 2, vec![1.7, -1.2]).unwrap();
+	/// assert_eq!(m.floor().data(), vec![1., -2.]);
+	/// ```
+	pub fn floor(&self) -> Matrix
+	{
+		self.mapped(|val, _, _| val.floor())
+	}
+
+	/// Returns a new matrix with every item rounded up.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 2, vec![1.2, -1.7]).unwrap();
+	/// assert_eq!(m.ceil().data(), vec![2., -1.]);
+	/// ```
+	pub fn ceil(&self) -> Matrix
+	{
+		self.mapped(|val, _, _| val.ceil())
+	}
+
+	/// Returns a new matrix with the sigmoid function `1 / (1 + e^-x)`
+	/// applied to every item.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 1, vec![0.]).unwrap();
+	/// assert_eq!(m.apply_sigmoid().data(), vec![0.5]);
+	/// ```
+	pub fn apply_sigmoid(&self) -> Matrix
+	{
+		self.mapped(|val, _, _| Activation::Sigmoid.apply(val))
+	}
+
+	/// Returns the weighted sum `weights[0] * matrices[0] + ...` of a list
+	/// of matrices. All matrices must have the same shape and there must
+	/// be exactly one weight per matrix.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let a = Matrix::from(1, 2, vec![1., 2.]).unwrap();
+	/// let b = Matrix::from(1, 2, vec![3., 4.]).unwrap();
+	/// let avg = Matrix::weighted_sum(vec![a, b], vec![0.5, 0.5]).unwrap();
+	/// assert_eq!(avg.data(), vec![2., 3.]);
+	/// ```
+	pub fn weighted_sum(matrices: Vec<Matrix>, weights: Vec<f64>) -> Result<Matrix, JsValue>
+	{
+		if matrices.is_empty() {
+			return Err(JsValue::from_str("Error: cannot sum an empty list of matrices"));
+		}
+		if matrices.len() != weights.len() {
+			return Err(JsValue::from_str("Error: number of weights must match number of matrices"));
+		}
+
+		let (rows, cols) = (matrices[0].rows, matrices[0].cols);
+		if matrices.iter().any(|m| m.rows != rows || m.cols != cols) {
+			return Err(JsValue::from_str("Error: all matrices must have the same dimensions"));
+		}
+
+		let mut result = Matrix::new(rows, cols);
+		for (m, weight) in matrices.iter().zip(weights.iter()) {
+			for (acc, val) in result.data.iter_mut().zip(m.data.iter()) {
+				*acc += weight * val;
+			}
+		}
+
+		Ok(result)
+	}
+
+	/// Returns a `rows`x`1` matrix where each row holds the weighted
+	/// average of that row's items. There must be one weight per column
+	/// and the weights must not sum to `0`.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 2, vec![2., 4.]).unwrap();
+	/// assert_eq!(m.weighted_col_mean(vec![3., 1.]).unwrap().data(), vec![2.5]);
+	/// ```
+	pub fn weighted_col_mean(&self, weights: Vec<f64>) -> Result<Matrix, JsValue>
+	{
+		if weights.len() != self.cols as usize {
+			return Err(JsValue::from_str("Error: number of weights must match the number of columns"));
+		}
+
+		let total: f64 = weights.iter().sum();
+		if total == 0_f64 {
+			return Err(JsValue::from_str("Error: weights must not sum to 0"));
+		}
+
+		let mut result = Matrix::new(self.rows, 1);
+		result.map(|_, row, _| {
+			let mut sum = 0_f64;
+			for (col, weight) in weights.iter().enumerate() {
+				sum += weight * self.get(row, col as u32);
+			}
+			sum / total
+		});
+		Ok(result)
+	}
+
+	/// Broadcasts the matrix to `rows`x`cols` following NumPy's rules:
+	/// every dimension must either already match the target or be `1`,
+	/// in which case it is repeated.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 1, vec![7.]).unwrap();
+	/// assert_eq!(m.broadcast_to(2, 2).unwrap().data(), vec![7.; 4]);
+	/// ```
+	pub fn broadcast_to(&self, rows: u32, cols: u32) -> Result<Matrix, JsValue>
+	{
+		if (self.rows != rows && self.rows != 1) || (self.cols != cols && self.cols != 1) {
+			return Err(JsValue::from_str("Error: matrix cannot be broadcast to the requested shape"));
+		}
+
+		let mut result = Matrix::new(rows, cols);
+		result.map(|_, row, col| self.get(row % self.rows, col % self.cols));
+		Ok(result)
+	}
+
+	/// Returns the `rows`x`rows` matrix of cosine similarities between
+	/// every pair of rows. Rows with a norm of `0` have no direction, so
+	/// their similarity to every row (including themselves) is `0`.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![1., 0., 0., 1.]).unwrap();
+	/// assert_eq!(m.pairwise_cosine().data(), vec![1., 0., 0., 1.]);
+	/// ```
+	pub fn pairwise_cosine(&self) -> Matrix
+	{
+		let norms: Vec<f64> = (0..self.rows)
+			.map(|row| (0..self.cols).map(|col| self.get(row, col).powi(2)).sum::<f64>().sqrt())
+			.collect();
+
+		let mut result = Matrix::new(self.rows, self.rows);
+		result.map(|_, a, b| {
+			let norm = norms[a as usize] * norms[b as usize];
+			if norm == 0_f64 {
+				return 0_f64;
+			}
+			let dot: f64 = (0..self.cols).map(|col| self.get(a, col) * self.get(b, col)).sum();
+			dot / norm
+		});
+		result
+	}
+
+	/// Returns the `rows`x`rows` matrix of Pearson correlations between
+	/// every pair of rows, treating each row as one feature and each
+	/// column as one observation. A feature with zero variance has no
+	/// correlation with any other feature, so its row and column are `0`
+	/// except for the `1` on the diagonal.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 3, vec![1., 2., 3., 6., 4., 2.]).unwrap();
+	/// assert!((m.correlation().get(0, 1) + 1.).abs() < 1e-12);
+	/// ```
+	pub fn correlation(&self) -> Matrix
+	{
+		let means: Vec<f64> = (0..self.rows)
+			.map(|row| (0..self.cols).map(|col| self.get(row, col)).sum::<f64>() / self.cols as f64)
+			.collect();
+
+		// the correlation is the cosine similarity of the centered rows
+		let centered = self.mapped(|val, row, _| val - means[row as usize]);
+		let mut result = centered.pairwise_cosine();
+		result.map(|val, a, b| if a == b { 1_f64 } else { val });
+		result
+	}
+
+	/// Returns a new matrix with a softmax applied to every row, so each
+	/// row becomes a probability distribution. The row maximum is
+	/// subtracted before exponentiating to avoid overflow.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 2, vec![1000., 1000.]).unwrap();
+	/// assert_eq!(m.row_softmax().data(), vec![0.5, 0.5]);
+	/// ```
+	pub fn row_softmax(&self) -> Matrix
+	{
+		let mut result = self.clone();
+		for row in 0..self.rows {
+			let start = self.calc_idx(row, 0);
+			let values = &mut result.data[start..start + self.cols as usize];
+
+			let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+			let mut sum = 0_f64;
+			for val in values.iter_mut() {
+				*val = (*val - max).exp();
+				sum += *val;
+			}
+			for val in values.iter_mut() {
+				*val /= sum;
+			}
+		}
+		result
+	}
+
+	/// Checks whether the matrix is row-stochastic: every item is
+	/// non-negative and every row sums to `1` (within `epsilon`).
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![0.5, 0.5, 0.1, 0.9]).unwrap();
+	/// assert!(m.is_row_stochastic(1e-9));
+	/// ```
+	pub fn is_row_stochastic(&self, epsilon: f64) -> bool
+	{
+		if self.data.iter().any(|val| *val < 0_f64) {
+			return false;
+		}
+
+		(0..self.rows).all(|row| {
+			let sum: f64 = (0..self.cols).map(|col| self.get(row, col)).sum();
+			(sum - 1_f64).abs() <= epsilon
+		})
+	}
+
+	/// Checks whether the matrix is column-stochastic: every item is
+	/// non-negative and every column sums to `1` (within `epsilon`).
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![0.5, 0.1, 0.5, 0.9]).unwrap();
+	/// assert!(m.is_col_stochastic(1e-9));
+	/// ```
+	pub fn is_col_stochastic(&self, epsilon: f64) -> bool
+	{
+		if self.data.iter().any(|val| *val < 0_f64) {
+			return false;
+		}
+
+		(0..self.cols).all(|col| {
+			let sum: f64 = (0..self.rows).map(|row| self.get(row, col)).sum();
+			(sum - 1_f64).abs() <= epsilon
+		})
+	}
+
+	/// Returns a copy of the matrix scaled towards a doubly stochastic
+	/// one with the Sinkhorn-Knopp algorithm: every iteration first
+	/// divides each row by its sum, then each column by its sum. For a
+	/// square matrix with positive items both row and column sums
+	/// approach `1`. Rows or columns that sum to `0` are left as they
+	/// are. Fails if any item is negative.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+	/// let s = m.sinkhorn(100).unwrap();
+	/// assert!(s.is_row_stochastic(1e-9) && s.is_col_stochastic(1e-9));
+	/// ```
+	pub fn sinkhorn(&self, iterations: u32) -> Result<Matrix, JsValue>
+	{
+		if self.data.iter().any(|val| *val < 0_f64) {
+			return Err(JsValue::from_str("Error: Sinkhorn normalization requires non-negative items"));
+		}
+
+		let mut result = self.clone();
+		for _ in 0..iterations {
+			let row_sums: Vec<f64> = (0..self.rows)
+				.map(|row| (0..self.cols).map(|col| result.get(row, col)).sum())
+				.collect();
+			result.map(|val, row, _| if row_sums[row as usize] > 0_f64 { val / row_sums[row as usize] } else { val });
+
+			let col_sums: Vec<f64> = (0..self.cols)
+				.map(|col| (0..self.rows).map(|row| result.get(row, col)).sum())
+				.collect();
+			result.map(|val, _, col| if col_sums[col as usize] > 0_f64 { val / col_sums[col as usize] } else { val });
+		}
+		Ok(result)
+	}
+
+	/// Checks whether the matrix is orthogonal: it is square and every
+	/// item of `Qᵀ·Q` is within `epsilon` of the identity matrix.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![0., 1., -1., 0.]).unwrap();
+	/// assert!(m.is_orthogonal(1e-9));
+	/// ```
+	pub fn is_orthogonal(&self, epsilon: f64) -> bool
+	{
+		if self.rows != self.cols {
+			return false;
+		}
+
+		let product = Matrix::mult(&self.transpose(), self).unwrap();
+		(0..self.rows).all(|row| (0..self.cols).all(|col| {
+			let expected = if row == col { 1_f64 } else { 0_f64 };
+			(product.get(row, col) - expected).abs() <= epsilon
+		}))
+	}
+
+	/// Returns the row indices that would sort the given column, either
+	/// ascending or descending. Equal values keep their original order.
+	/// Fails if `col` is out of range.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(3, 1, vec![0.5, 0.1, 0.9]).unwrap();
+	/// assert_eq!(m.argsort_column(0, false).unwrap(), vec![1, 0, 2]);
+	/// ```
+	pub fn argsort_column(&self, col: u32, descending: bool) -> Result<Vec<u32>, JsValue>
+	{
+		if col >= self.cols {
+			return Err(JsValue::from_str("Error: column index out of range"));
+		}
+
+		let mut indices: Vec<u32> = (0..self.rows).collect();
+		indices.sort_by(|a, b| {
+			let ordering = self.get(*a, col).partial_cmp(&self.get(*b, col))
+				.unwrap_or(std::cmp::Ordering::Equal);
+			if descending { ordering.reverse() } else { ordering }
+		});
+		Ok(indices)
+	}
+
+	/// Returns a new matrix whose row `i` is row `order[i]` of this matrix,
+	/// e.g. to apply the result of `argsort_column`. `order` must have
+	/// one entry per row and every entry must be a valid row index.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 1, vec![1., 2.]).unwrap();
+	/// assert_eq!(m.permute_rows(vec![1, 0]).unwrap().data(), vec![2., 1.]);
+	/// ```
+	pub fn permute_rows(&self, order: Vec<u32>) -> Result<Matrix, JsValue>
+	{
+		if order.len() != self.rows as usize {
+			return Err(JsValue::from_str("Error: length of order must match the number of rows"));
+		}
+		if order.iter().any(|row| *row >= self.rows) {
+			return Err(JsValue::from_str("Error: row index out of range"));
+		}
+
+		let mut result = Matrix::new(self.rows, self.cols);
+		result.map(|_, row, col| self.get(order[row as usize], col));
+		Ok(result)
+	}
+
+	/// Rearranges the (possibly overlapping) `kernel_rows`x`kernel_cols`
+	/// patches of the matrix into columns, moving the kernel by `stride`
+	/// between patches. Each column holds one patch in row-major order and
+	/// the patches are ordered left to right, top to bottom. A convolution
+	/// then becomes a single `mult` of a flattened `1`x`(kernel_rows *
+	/// kernel_cols)` kernel with the result.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let image = Matrix::from(2, 3, vec![1., 2., 3., 4., 5., 6.]).unwrap();
+	/// let cols = image.im2col(2, 2, 1).unwrap();
+	/// // two patches: |1, 2|  and  |2, 3|
+	/// //              |4, 5|       |5, 6|
+	/// assert_eq!(cols.data(), vec![1., 2., 2., 3., 4., 5., 5., 6.]);
+	/// ```
+	pub fn im2col(&self, kernel_rows: u32, kernel_cols: u32, stride: u32) -> Result<Matrix, JsValue>
+	{
+		if kernel_rows == 0 || kernel_cols == 0 || stride == 0 {
+			return Err(JsValue::from_str("Error: kernel size and stride must be greater than 0"));
+		}
+		if kernel_rows > self.rows || kernel_cols > self.cols {
+			return Err(JsValue::from_str("Error: kernel must not be larger than the matrix"));
+		}
+
+		let out_cols = (self.cols - kernel_cols) / stride + 1;
+		let out_rows = (self.rows - kernel_rows) / stride + 1;
+
+		let mut result = Matrix::new(kernel_rows * kernel_cols, out_rows * out_cols);
+		result.map(|_, row, col| {
+			let (patch_row, patch_col) = (col / out_cols, col % out_cols);
+			let (kernel_row, kernel_col) = (row / kernel_cols, row % kernel_cols);
+			self.get(patch_row * stride + kernel_row, patch_col * stride + kernel_col)
+		});
+		Ok(result)
+	}
+
+	/// Treats the matrix as a grayscale image and blurs it with a
+	/// Gaussian kernel of standard deviation `sigma`, which reaches
+	/// `⌈3 * sigma⌉` items in every direction. The kernel is separable,
+	/// so it is applied to the rows first and then to the columns. At the
+	/// borders the image is mirrored (`c b a | a b c`), which keeps the
+	/// total intensity close to the original. A `sigma` that is not a
+	/// finite, positive number returns an unchanged copy.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 3, vec![0., 3., 0.]).unwrap();
+	/// let blurred = m.gaussian_blur(1.);
+	/// assert!(blurred.get(0, 0) > 0. && blurred.get(0, 1) < 3.);
+	/// ```
+	pub fn gaussian_blur(&self, sigma: f64) -> Matrix
+	{
+		if !sigma.is_finite() || sigma <= 0_f64 || self.data.is_empty() {
+			return self.clone();
+		}
+
+		let radius = (3_f64 * sigma).ceil() as i64;
+		let kernel: Vec<f64> = (-radius..=radius).map(|x| (-(x * x) as f64 / (2_f64 * sigma * sigma)).exp()).collect();
+		let sum: f64 = kernel.iter().sum();
+		let kernel: Vec<f64> = kernel.iter().map(|k| k / sum).collect();
+
+		// mirrors an out-of-range index back into `0..len`
+		let reflect = |idx: i64, len: u32| -> u32 {
+			let period = 2 * len as i64;
+			let idx = idx.rem_euclid(period);
+			(if idx < len as i64 { idx } else { period - 1 - idx }) as u32
+		};
+		let blur = |source: &Matrix, horizontal: bool| source.mapped(|_, row, col| {
+			kernel.iter().enumerate().map(|(k, weight)| {
+				let offset = k as i64 - radius;
+				if horizontal {
+					weight * source.get(row, reflect(col as i64 + offset, source.cols))
+				} else {
+					weight * source.get(reflect(row as i64 + offset, source.rows), col)
+				}
+			}).sum()
+		});
+
+		blur(&blur(self, true), false)
+	}
+
+	/// Treats the matrix as an image and scales it up to `target_rows` x
+	/// `target_cols` with nearest-neighbor interpolation: every item of
+	/// the result copies the item its position falls on in the original.
+	/// Integer factors therefore replicate every item into a block. Fails
+	/// if a target dimension is smaller than the original one, or if an
+	/// empty matrix should be scaled to a non-empty one.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 2, vec![1., 2.]).unwrap();
+	/// assert_eq!(m.upscale(1, 4).unwrap().data(), vec![1., 1., 2., 2.]);
+	/// ```
+	pub fn upscale(&self, target_rows: u32, target_cols: u32) -> Result<Matrix, JsValue>
+	{
+		if target_rows < self.rows || target_cols < self.cols {
+			return Err(JsValue::from_str(&format!(
+				"Error: cannot upscale a {}x{} matrix to {}x{}", self.rows, self.cols, target_rows, target_cols
+			)));
+		}
+		if self.data.is_empty() && target_rows > 0 && target_cols > 0 {
+			return Err(JsValue::from_str("Error: cannot upscale an empty matrix"));
+		}
+
+		let mut result = Matrix::new(target_rows, target_cols);
+		result.map(|_, row, col| {
+			let src_row = (row as u64 * self.rows as u64 / target_rows as u64) as u32;
+			let src_col = (col as u64 * self.cols as u64 / target_cols as u64) as u32;
+			self.get(src_row, src_col)
+		});
+		Ok(result)
+	}
+
+	/// Returns the matrix as a JSON array of rows, e.g. `[[1,2],[3,4]]`,
+	/// so JS code can work with it row by row after `JSON.parse`.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+	/// assert_eq!(m.to_rows_json(), "[[1.0,2.0],[3.0,4.0]]");
+	/// ```
+	pub fn to_rows_json(&self) -> String
+	{
+		serde_json::to_string(&self.to_rows()).unwrap()
+	}
+
+	/// Builds a matrix from a JSON array of rows, the inverse of
+	/// `to_rows_json`. The number of columns is taken from the first row.
+	/// Fails if the string is not a JSON array of number arrays, or if
+	/// the rows are empty or don't all have the same length.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from_rows_json("[[1, 2, 3], [4, 5, 6]]").unwrap();
+	/// assert_eq!(m.cols(), 3);
+	/// ```
+	pub fn from_rows_json(json: &str) -> Result<Matrix, JsValue>
+	{
+		let rows: Vec<Vec<f64>> = serde_json::from_str(json)
+			.map_err(|e| JsValue::from_str(&format!("Error: could not parse rows: {}", e)))?;
+		Matrix::from_rows(rows)
+	}
+}
+
+/// Methods in this `impl` are **not** accessable from JavaScript.
+impl Matrix
+{
+	/// Builds a matrix from a list of rows. Every row must have the
+	/// same length and there must be at least one row.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from_rows(vec![vec![1., 2., 3.], vec![4., 5., 6.]]).unwrap();
+	/// assert_eq!(m.rows(), 2);
+	/// assert_eq!(m.data(), vec![1., 2., 3., 4., 5., 6.]);
+	/// ```
+	pub fn from_rows(rows: Vec<Vec<f64>>) -> Result<Matrix, JsValue>
+	{
+		if rows.is_empty() || rows[0].is_empty() {
+			return Err(JsValue::from_str("Error: cannot build a matrix from empty rows"));
+		}
+
+		let cols = rows[0].len();
+		if rows.iter().any(|row| row.len() != cols) {
+			return Err(JsValue::from_str("Error: all rows must have the same length"));
+		}
+
+		Matrix::from(rows.len() as u32, cols as u32, rows.concat())
+	}
+
+	/// Returns the matrix as a list of rows, the inverse of `from_rows`.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+	/// assert_eq!(m.to_rows(), vec![vec![1., 2.], vec![3., 4.]]);
+	/// ```
+	pub fn to_rows(&self) -> Vec<Vec<f64>>
+	{
+		self.data.chunks(self.cols.max(1) as usize).map(|row| row.to_vec()).collect()
+	}
+
+	/// Builds a matrix from a list of columns. Every column must have the
+	/// same length and there must be at least one column.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from_cols(vec![vec![1., 4.], vec![2., 5.], vec![3., 6.]]).unwrap();
+	/// assert_eq!(m.cols(), 3);
+	/// assert_eq!(m.data(), vec![1., 2., 3., 4., 5., 6.]);
+	/// ```
+	pub fn from_cols(cols: Vec<Vec<f64>>) -> Result<Matrix, JsValue>
+	{
+		if cols.is_empty() || cols[0].is_empty() {
+			return Err(JsValue::from_str("Error: cannot build a matrix from empty columns"));
+		}
+
+		let rows = cols[0].len();
+		if cols.iter().any(|col| col.len() != rows) {
+			return Err(JsValue::from_str("Error: all columns must have the same length"));
+		}
+
+		let mut result = Matrix::new(rows as u32, cols.len() as u32);
+		result.map(|_, row, col| cols[col as usize][row as usize]);
+		Ok(result)
+	}
+
+	/// Assembles a 2D grid of blocks into one matrix. All blocks in a grid
+	/// row must have the same number of rows, all blocks in a grid column
+	/// the same number of columns, and every grid row must have the same
+	/// number of blocks.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let a = Matrix::from(1, 1, vec![1.]).unwrap();
+	/// let b = Matrix::from(1, 1, vec![2.]).unwrap();
+	/// let m = Matrix::from_blocks(vec![vec![a, b]]).unwrap();
+	/// assert_eq!(m.data(), vec![1., 2.]);
+	/// ```
+	pub fn from_blocks(blocks: Vec<Vec<Matrix>>) -> Result<Matrix, JsValue>
+	{
+		if blocks.is_empty() || blocks[0].is_empty() {
+			return Err(JsValue::from_str("Error: cannot build a matrix from an empty grid of blocks"));
+		}
+
+		let col_widths: Vec<u32> = blocks[0].iter().map(|block| block.cols).collect();
+		for grid_row in blocks.iter() {
+			if grid_row.len() != col_widths.len() {
+				return Err(JsValue::from_str("Error: all grid rows must have the same number of blocks"));
+			}
+			let height = grid_row[0].rows;
+			for (block, width) in grid_row.iter().zip(col_widths.iter()) {
+				if block.rows != height || block.cols != *width {
+					return Err(JsValue::from_str("Error: block dimensions do not line up"));
+				}
+			}
+		}
+
+		let rows: u32 = blocks.iter().map(|grid_row| grid_row[0].rows).sum();
+		let cols: u32 = col_widths.iter().sum();
+		let mut result = Matrix::new(rows, cols);
+
+		let mut row_offset = 0;
+		for grid_row in blocks.iter() {
+			let mut col_offset = 0;
+			for block in grid_row.iter() {
+				for r in 0..block.rows {
+					for c in 0..block.cols {
+						let idx = result.calc_idx(row_offset + r, col_offset + c);
+						result.data[idx] = block.get(r, c);
+					}
+				}
+				col_offset += block.cols;
+			}
+			row_offset += grid_row[0].rows;
+		}
+
+		Ok(result)
+	}
+
+	/// Combines two matrices of the same shape item by item, calling `f`
+	/// with the items of `a` and `b` at each position. Fails with
+	/// `MatrixError::DimensionMismatch` if the shapes differ.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let a = Matrix::from(1, 2, vec![1., 2.]).unwrap();
+	/// let b = Matrix::from(1, 2, vec![3., 4.]).unwrap();
+	/// let c = Matrix::zip_map(&a, &b, |x, y| x * 10. + y).unwrap();
+	/// assert_eq!(c.data(), vec![13., 24.]);
+	/// ```
+	pub fn zip_map<F>(a: &Matrix, b: &Matrix, f: F) -> Result<Matrix, MatrixError>
+	where F: Fn(f64, f64) -> f64
+	{
+		if a.rows != b.rows || a.cols != b.cols {
+			return Err(MatrixError::DimensionMismatch);
+		}
+
+		let mut result = a.clone();
+		for (val, other) in result.data.iter_mut().zip(b.data.iter()) {
+			*val = f(*val, *other);
+		}
+		Ok(result)
+	}
+
+	/// Splits the matrix into a top part with the first `row` rows and a
+	/// bottom part with the remaining rows. `row` must lie strictly
+	/// between `0` and the number of rows, so neither part is empty.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(3, 1, vec![1., 2., 3.]).unwrap();
+	/// let (top, bottom) = m.split_rows_at(1).unwrap();
+	/// assert_eq!(top.data(), vec![1.]);
+	/// assert_eq!(bottom.data(), vec![2., 3.]);
+	/// ```
+	pub fn split_rows_at(&self, row: u32) -> Result<(Matrix, Matrix), JsValue>
+	{
+		if row == 0 || row >= self.rows {
+			return Err(JsValue::from_str("Error: split row must be between 1 and rows - 1"));
+		}
+
+		let split = self.calc_idx(row, 0);
+		let top = Matrix::from(row, self.cols, self.data[..split].to_vec())?;
+		let bottom = Matrix::from(self.rows - row, self.cols, self.data[split..].to_vec())?;
+		Ok((top, bottom))
+	}
+
+	/// Splits the matrix into a left part with the first `col` columns
+	/// and a right part with the remaining columns. `col` must lie
+	/// strictly between `0` and the number of columns.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(1, 3, vec![1., 2., 3.]).unwrap();
+	/// let (left, right) = m.split_cols_at(2).unwrap();
+	/// assert_eq!(left.data(), vec![1., 2.]);
+	/// assert_eq!(right.data(), vec![3.]);
+	/// ```
+	pub fn split_cols_at(&self, col: u32) -> Result<(Matrix, Matrix), JsValue>
+	{
+		if col == 0 || col >= self.cols {
+			return Err(JsValue::from_str("Error: split column must be between 1 and cols - 1"));
+		}
+
+		let mut left = Matrix::new(self.rows, col);
+		left.map(|_, r, c| self.get(r, c));
+		let mut right = Matrix::new(self.rows, self.cols - col);
+		right.map(|_, r, c| self.get(r, c + col));
+		Ok((left, right))
+	}
+
+	/// Maps over each position in the matrix (starting top-left
+	/// going left to right). A callback is called on each item
+	/// of the matrix with the current `value`, its `row` and
+	/// its `column`.
+	pub fn map<F>(&mut self, mut cb: F)
+	where F: FnMut(f64, u32, u32) -> f64
+	{
+		for i in 0..self.rows {
+			for j in 0..self.cols {
+				let idx = self.calc_idx(i, j);
+				self.data[idx] = cb(self.data[idx], i, j);
+			}
+		}
+	}
+
+	/// Like `map`, but leaves the matrix untouched and returns the
+	/// result as a new matrix instead.
+	pub fn mapped<F>(&self, cb: F) -> Matrix
+	where F: FnMut(f64, u32, u32) -> f64
+	{
+		let mut result = self.clone();
+		result.map(cb);
+		result
+	}
+
+	/// Computes the eigenvalues of a symmetric, square matrix with the
+	/// cyclic Jacobi method. The eigenvalues are returned in the order
+	/// they appear on the diagonal after convergence.
+	fn symmetric_eigenvalues(&self) -> Vec<f64>
+	{
+		let n = self.rows;
+		let mut a = self.clone();
+
+		for _ in 0..100 {
+			let mut off_diagonal = 0_f64;
+			for i in 0..n {
+				for j in 0..n {
+					if i != j {
+						off_diagonal += a.get(i, j) * a.get(i, j);
+					}
+				}
+			}
+			if off_diagonal < 1e-22 {
+				break;
+			}
+
+			for p in 0..n {
+				for q in (p + 1)..n {
+					let apq = a.get(p, q);
+					if apq == 0_f64 {
+						continue;
+					}
+
+					let theta = (a.get(q, q) - a.get(p, p)) / (2_f64 * apq);
+					let t = theta.signum() / (theta.abs() + (theta * theta + 1_f64).sqrt());
+					let c = 1_f64 / (t * t + 1_f64).sqrt();
+					let s = t * c;
+
+					for k in 0..n {
+						let akp = a.get(k, p);
+						let akq = a.get(k, q);
+						let idx_p = a.calc_idx(k, p);
+						let idx_q = a.calc_idx(k, q);
+						a.data[idx_p] = c * akp - s * akq;
+						a.data[idx_q] = s * akp + c * akq;
+					}
+					for k in 0..n {
+						let apk = a.get(p, k);
+						let aqk = a.get(q, k);
+						let idx_p = a.calc_idx(p, k);
+						let idx_q = a.calc_idx(q, k);
+						a.data[idx_p] = c * apk - s * aqk;
+						a.data[idx_q] = s * apk + c * aqk;
+					}
+				}
+			}
+		}
+
+		(0..n).map(|i| a.get(i, i)).collect()
+	}
+
+	/// Returns both the determinant and the inverse of a square matrix,
+	/// computed from a single LU decomposition. Fails if the matrix is
+	/// not square or is singular.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![4., 7., 2., 6.]).unwrap();
+	/// let (det, inv) = m.lu_solve_all().unwrap();
+	/// assert!((det - 10.).abs() < 1e-9);
+	/// assert!((inv.get(0, 0) - 0.6).abs() < 1e-9);
+	/// ```
+	pub fn lu_solve_all(&self) -> Result<(f64, Matrix), JsValue>
+	{
+		let (lu, perm, sign) = self.lu_decompose()?;
+		let n = self.rows;
+
+		let mut det = sign;
+		for i in 0..n {
+			det *= lu.get(i, i);
+		}
+
+		let mut inverse = Matrix::new(n, n);
+		for col in 0..n {
+			// forward substitution with the unit lower triangle: L·y = P·e_col
+			let mut y = vec![0_f64; n as usize];
+			for i in 0..n {
+				let mut sum = if perm[i as usize] == col { 1_f64 } else { 0_f64 };
+				for k in 0..i {
+					sum -= lu.get(i, k) * y[k as usize];
+				}
+				y[i as usize] = sum;
+			}
+
+			// back substitution with the upper triangle: U·x = y
+			for i in (0..n).rev() {
+				let mut sum = y[i as usize];
+				for k in (i + 1)..n {
+					sum -= lu.get(i, k) * inverse.get(k, col);
+				}
+				let idx = inverse.calc_idx(i, col);
+				inverse.data[idx] = sum / lu.get(i, i);
+			}
+		}
+
+		Ok((det, inverse))
+	}
+
+	/// Returns the natural logarithm of the absolute determinant together
+	/// with the determinant's sign (`-1` or `1`), computed by summing the
+	/// logs of the LU pivots. Unlike the determinant itself this doesn't
+	/// overflow for large matrices. Fails if the matrix is not square or
+	/// is singular.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![0., 2., 3., 0.]).unwrap();
+	/// let (ln_det, sign) = m.log_determinant().unwrap();
+	/// assert!((ln_det - 6_f64.ln()).abs() < 1e-12);
+	/// assert_eq!(sign, -1);
+	/// ```
+	pub fn log_determinant(&self) -> Result<(f64, i8), JsValue>
+	{
+		let (lu, _, mut sign) = self.lu_decompose()?;
+
+		let mut ln_det = 0_f64;
+		for i in 0..self.rows {
+			let pivot = lu.get(i, i);
+			ln_det += pivot.abs().ln();
+			sign *= pivot.signum();
+		}
+
+		Ok((ln_det, sign as i8))
+	}
+
+	/// Returns the Moore-Penrose pseudo-inverse. For a tall (or square)
+	/// matrix this is `(AᵀA)⁻¹Aᵀ`, for a wide matrix `Aᵀ(AAᵀ)⁻¹`. Fails
+	/// if `AᵀA` (respectively `AAᵀ`) is singular, i.e. the matrix does
+	/// not have full rank.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 1, vec![1., 1.]).unwrap();
+	/// assert_eq!(m.pseudo_inverse().unwrap().data(), vec![0.5, 0.5]);
+	/// ```
+	pub fn pseudo_inverse(&self) -> Result<Matrix, JsValue>
+	{
+		let transposed = self.transpose();
+		if self.rows >= self.cols {
+			let (_, normal_inverse) = Matrix::mult(&transposed, self)?.lu_solve_all()?;
+			Matrix::mult(&normal_inverse, &transposed)
+		} else {
+			let (_, normal_inverse) = Matrix::mult(self, &transposed)?.lu_solve_all()?;
+			Matrix::mult(&transposed, &normal_inverse)
+		}
+	}
+
+	/// Computes the QR decomposition with Householder reflections and
+	/// returns `(Q, R)`, where `Q` is an orthogonal `rows`x`rows` matrix,
+	/// `R` is an upper-triangular `rows`x`cols` matrix and `Q·R == self`.
+	/// Fails on an empty matrix.
+	/// ```
+	/// use neural_net_rs::Matrix;
+	/// let m = Matrix::from(2, 2, vec![3., 1., 4., 2.]).unwrap();
+	/// let (q, r) = m.qr().unwrap();
+	/// assert!(r.get(1, 0).abs() < 1e-12);
+	/// ```
+	pub fn qr(&self) -> Result<(Matrix, Matrix), JsValue>
+	{
+		if self.rows == 0 || self.cols == 0 {
+			return Err(JsValue::from_str("Error: cannot decompose an empty matrix"));
+		}
+
+		let (m, n) = (self.rows, self.cols);
+		let mut r = self.clone();
+		let mut q = Matrix::new(m, m);
+		q.map(|_, row, col| if row == col { 1_f64 } else { 0_f64 });
+
+		for k in 0..n.min(m - 1) {
+			let norm = (k..m).map(|i| r.get(i, k).powi(2)).sum::<f64>().sqrt();
+			if norm == 0_f64 {
+				continue;
+			}
+
+			// reflect x = r[k.., k] onto -sign(x0) * |x| * e_0
+			let alpha = if r.get(k, k) > 0_f64 { -norm } else { norm };
+			let mut v: Vec<f64> = (k..m).map(|i| r.get(i, k)).collect();
+			v[0] -= alpha;
+			let v_norm = v.iter().map(|x| x * x).sum::<f64>().sqrt();
+			if v_norm == 0_f64 {
+				continue;
+			}
+			for x in v.iter_mut() {
+				*x /= v_norm;
+			}
+
+			// r = (I - 2vvᵀ)·r
+			for col in 0..n {
+				let dot: f64 = (k..m).map(|i| v[(i - k) as usize] * r.get(i, col)).sum();
+				for i in k..m {
+					let idx = r.calc_idx(i, col);
+					r.data[idx] -= 2_f64 * v[(i - k) as usize] * dot;
+				}
+			}
+
+			// q = q·(I - 2vvᵀ)
+			for row in 0..m {
+				let dot: f64 = (k..m).map(|i| q.get(row, i) * v[(i - k) as usize]).sum();
+				for i in k..m {
+					let idx = q.calc_idx(row, i);
+					q.data[idx] -= 2_f64 * dot * v[(i - k) as usize];
+				}
+			}
+		}
+
+		Ok((q, r))
+	}
+
+	/// Decomposes a square matrix into `P·A = L·U` using partial pivoting.
+	/// Returns `L` and `U` packed into one matrix (the unit diagonal of `L`
+	/// is implied), the row permutation and the sign of that permutation.
+	fn lu_decompose(&self) -> Result<(Matrix, Vec<u32>, f64), JsValue>
+	{
+		if self.rows != self.cols {
+			return Err(JsValue::from_str("Error: LU decomposition requires a square matrix"));
+		}
+
+		let n = self.rows;
+		let mut lu = self.clone();
+		let mut perm: Vec<u32> = (0..n).collect();
+		let mut sign = 1_f64;
+
+		for k in 0..n {
+			let mut pivot_row = k;
+			for i in (k + 1)..n {
+				if lu.get(i, k).abs() > lu.get(pivot_row, k).abs() {
+					pivot_row = i;
+				}
+			}
+
+			if lu.get(pivot_row, k).abs() < 1e-12 {
+				return Err(JsValue::from_str("Error: matrix is singular"));
+			}
+
+			if pivot_row != k {
+				for j in 0..n {
+					let idx_a = lu.calc_idx(k, j);
+					let idx_b = lu.calc_idx(pivot_row, j);
+					lu.data.swap(idx_a, idx_b);
+				}
+				perm.swap(k as usize, pivot_row as usize);
+				sign = -sign;
+			}
+
+			for i in (k + 1)..n {
+				let factor = lu.get(i, k) / lu.get(k, k);
+				let idx = lu.calc_idx(i, k);
+				lu.data[idx] = factor;
+				for j in (k + 1)..n {
+					let idx = lu.calc_idx(i, j);
+					lu.data[idx] -= factor * lu.get(k, j);
+				}
+			}
+		}
+
+		Ok((lu, perm, sign))
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::{Matrix, MatrixError};
+
+	#[test]
+	fn matrix_new()
+	{
+		let m = Matrix::new(3, 4);
+		assert_eq!(m.rows(), 3);
+		assert_eq!(m.cols(), 4);
+		assert_eq!(m.data(), vec![0_f64; 12]);
+	}
+
+	#[test]
+	fn matrix_from()
+	{
+		let v = vec![1., 2., 3., 4., 5., 6.];
+		let m1 = Matrix::from(2, 3, v.clone()).unwrap();
+		let m2 = Matrix::from(3, 2, v.clone()).unwrap();
+		assert_eq!(m1.rows(), 2);
+		assert_eq!(m1.cols(), 3);
+		assert_eq!(m1.data(), v);
+		assert_eq!(m2.data(), v);
+	}
+
+	#[test]
+	fn getters()
+	{
+		let mut m = Matrix::new(2, 3);
+		m.map(|_, r, c| (r + c) as f64);
+		assert_eq!(m.rows(), 2); // |0, 1, 2|
+		assert_eq!(m.cols(), 3); // |1, 2, 3|
+		assert_eq!(m.data(), vec![0., 1., 2., 1., 2., 3.]);
+		assert_eq!(m.get(0, 1), 1.);
+		assert_eq!(m.get(1, 1), 2.);
+	}
+
+	#[test]
+	fn try_get()
+	{
+		let m = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+		assert_eq!(m.try_get(1, 0).unwrap(), 3.);
+		assert_eq!(m.try_get(1, 1).unwrap(), 4.);
+	}
+
+	#[test]
+	fn set()
+	{
+		let mut m = Matrix::new(2, 3);
+		m.set(0, 2, 5.);
+		m.set(1, 0, -1.);
+		assert_eq!(m.get(0, 2), 5.);
+		assert_eq!(m.data(), vec![0., 0., 5., -1., 0., 0.]);
+	}
+
+	#[test]
+	fn scale()
+	{
+		let mut m = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+		m.scale(2.);
+		assert_eq!(m.data(), vec![2., 4., 6., 8.]);
+	}
+
+	#[test]
+	fn randomize()
+	{
+		let mut m = Matrix::new(4, 5);
+		m.randomize();
+		assert!(m.data().iter().all(|v| *v >= -1. && *v <= 1.));
+		assert!(m.data().iter().any(|v| *v != 0.));
+	}
+
+	#[test]
+	fn random()
+	{
+		let a = Matrix::random(3, 4, 7);
+		let b = Matrix::random(3, 4, 7);
+		assert_eq!(a.data(), b.data());
+		assert!(a.data().iter().all(|v| *v >= -1. && *v <= 1.));
+		assert_ne!(a.data(), Matrix::random(3, 4, 8).data());
+	}
+
+	#[test]
+	fn transpose()
+	{
+		let m = Matrix::from(2, 3, vec![1., 2., 3., 4., 5., 6.]).unwrap();
+		let t = m.transpose();
+		assert_eq!(t.rows(), 3);
+		assert_eq!(t.cols(), 2);
+		for r in 0..2 {
+			for c in 0..3 {
+				assert_eq!(t.get(c, r), m.get(r, c));
+			}
+		}
+		assert_eq!(t.transpose().data(), m.data());
+	}
+
+	#[test]
+	fn calc_idx()
+	{
+		let m = Matrix::new(3, 4);
+		// |0, 1,  2,  3|
+		// |4, 5,  6,  7|
+		// |8, 9, 10, 12|
+		assert_eq!(m.calc_idx(0, 0), 0);
+		assert_eq!(m.calc_idx(0, 2), 2);
+		assert_eq!(m.calc_idx(1, 1), 5);
+		assert_eq!(m.calc_idx(2, 2), 10);
+	}
+
+	#[test]
+	fn mult()
+	{
+		let a = Matrix::from(2, 3, vec![5., 1., 2., 3., 2., 6.]).unwrap();
+		let b = Matrix::from(3, 2, vec![8., 7., 4., 4., 5., 1.]).unwrap();
+
+		let c = Matrix::mult(&a, &b).unwrap();
+		assert_eq!(c.rows(), 2);
+		assert_eq!(c.cols(), 2);
+		assert_eq!(c.data(), vec![54., 41., 62., 35.]);
+	}
+
+	#[test]
+	fn add()
+	{
+		let a = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+		let b = Matrix::from(2, 2, vec![0.5, -2., 7., 1.]).unwrap();
+		let c = Matrix::add(&a, &b).unwrap();
+		assert_eq!(c.rows(), 2);
+		assert_eq!(c.cols(), 2);
+		assert_eq!(c.data(), vec![1.5, 0., 10., 5.]);
+	}
+
+	#[test]
+	fn sub()
+	{
+		let a = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+		let b = Matrix::from(2, 2, vec![0.5, -2., 7., 1.]).unwrap();
+		let c = Matrix::sub(&a, &b).unwrap();
+		assert_eq!(c.data(), vec![0.5, 4., -4., 3.]);
+	}
+
+	#[test]
+	fn hadamard()
+	{
+		let a = Matrix::from(2, 2, vec![1., -2., 0.5, 4.]).unwrap();
+		let b = Matrix::from(2, 2, vec![3., 3., 4., 0.]).unwrap();
+		let c = Matrix::hadamard(&a, &b).unwrap();
+		assert_eq!(c.rows(), 2);
+		assert_eq!(c.cols(), 2);
+		assert_eq!(c.data(), vec![3., -6., 2., 0.]);
+	}
+
+	#[test]
+	fn frobenius_distance()
+	{
+		let a = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+		let b = Matrix::from(2, 2, vec![0., 2., 5., 2.]).unwrap();
+		// differences 1, 0, -2, 2 -> sqrt(9)
+		assert_eq!(Matrix::frobenius_distance(&a, &b).unwrap(), 3.);
+		assert_eq!(Matrix::frobenius_distance(&a, &a).unwrap(), 0.);
+	}
+
+	#[test]
+	fn mult3()
+	{
+		let a = Matrix::from(3, 1, vec![1., 2., 3.]).unwrap();
+		let b = Matrix::from(1, 3, vec![4., 5., 6.]).unwrap();
+		let c = Matrix::from(3, 2, vec![1., 0., 0., 1., 1., 1.]).unwrap();
+
+		let left_first = Matrix::mult(&Matrix::mult(&a, &b).unwrap(), &c).unwrap();
+		let right_first = Matrix::mult(&a, &Matrix::mult(&b, &c).unwrap()).unwrap();
+		let fused = Matrix::mult3(&a, &b, &c).unwrap();
+		assert_eq!(fused.data(), left_first.data());
+		assert_eq!(fused.data(), right_first.data());
+
+		// (a·b)·c costs 9 + 18 multiplications, a·(b·c) only 6 + 6
+		assert_eq!(Matrix::mult3_costs(&a, &b, &c), (27, 12));
+	}
+
+	#[test]
+	fn map()
+	{
+		let mut m = Matrix::from(2, 3, vec![1., 2., 3., 4., 5., 6.]).unwrap();
+		m.map(|val, r, c| val * (c * r) as f64);
+		assert_eq!(m.data(), vec![0., 0., 0., 0., 5., 12.]);
+	}
+
+	#[test]
+	fn mapped()
+	{
+		let m = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+		let doubled = m.mapped(|val, _, _| val * 2.);
+		assert_eq!(doubled.data(), vec![2., 4., 6., 8.]);
+		assert_eq!(m.data(), vec![1., 2., 3., 4.]);
+	}
+
+	#[test]
+	fn rank()
+	{
+		let full = Matrix::from(3, 3, vec![2., 1., 0., 1., 3., 1., 0., 1., 4.]).unwrap();
+		assert_eq!(full.rank(1e-9), 3);
+
+		// the third row is the sum of the first two
+		let deficient = Matrix::from(3, 3, vec![1., 2., 3., 0., 1., 1., 1., 3., 4.]).unwrap();
+		assert_eq!(deficient.rank(1e-9), 2);
+
+		assert_eq!(Matrix::from(2, 3, vec![1., 0., 2., 0., 1., 5.]).unwrap().rank(1e-9), 2);
+		assert_eq!(Matrix::new(2, 2).rank(1e-9), 0);
+	}
+
+	#[test]
+	fn identity()
+	{
+		let m = Matrix::identity(3);
+		assert_eq!(m.data(), vec![1., 0., 0., 0., 1., 0., 0., 0., 1.]);
+		assert!(m.is_orthogonal(0.));
+		assert_eq!(Matrix::identity(0).data(), Vec::<f64>::new());
+	}
+
+	#[test]
+	fn approx_eq()
+	{
+		let a = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+		let close = Matrix::from(2, 2, vec![1.0005, 2., 3., 3.9995]).unwrap();
+		assert!(Matrix::approx_eq(&a, &close, 1e-3));
+		assert!(!Matrix::approx_eq(&a, &close, 1e-4));
+
+		let reshaped = Matrix::from(1, 4, vec![1., 2., 3., 4.]).unwrap();
+		assert!(!Matrix::approx_eq(&a, &reshaped, 1.));
+	}
+
+	#[test]
+	fn exp_matrix()
+	{
+		let zero = Matrix::new(3, 3);
+		assert_eq!(zero.exp_matrix(10).unwrap().data(), Matrix::identity(3).data());
+
+		let diagonal = Matrix::from(2, 2, vec![1., 0., 0., -2.]).unwrap();
+		let expected = Matrix::from(2, 2, vec![1_f64.exp(), 0., 0., (-2_f64).exp()]).unwrap();
+		assert!(Matrix::approx_eq(&diagonal.exp_matrix(30).unwrap(), &expected, 1e-9));
+	}
+
+	#[test]
+	fn condition_estimate()
+	{
+		let identity = Matrix::identity(3);
+		assert!((identity.condition_estimate().unwrap() - 1.).abs() < 1e-9);
+
+		// a wide matrix with full row rank is fine, and so is its transpose
+		let wide = Matrix::from(2, 3, vec![1., 0., 0., 0., 2., 0.]).unwrap();
+		assert!((wide.condition_estimate().unwrap() - 2.).abs() < 1e-9);
+		let tall = Matrix::from(3, 2, vec![1., 0., 0., 2., 0., 0.]).unwrap();
+		assert!((tall.condition_estimate().unwrap() - 2.).abs() < 1e-9);
+
+		let near_singular = Matrix::from(2, 2, vec![1., 1., 1., 1.0001]).unwrap();
+		assert!(near_singular.condition_estimate().unwrap() > 1e4);
+	}
+
+	#[test]
+	fn cholesky()
+	{
+		let m = Matrix::from(3, 3, vec![
+			4., 12., -16.,
+			12., 37., -43.,
+			-16., -43., 98.
+		]).unwrap();
+		let lower = m.cholesky().unwrap();
+		assert_eq!(lower.data(), vec![
+			2., 0., 0.,
+			6., 1., 0.,
+			-8., 5., 3.
+		]);
+
+		let mut reconstructed = Matrix::new(3, 3);
+		reconstructed.map(|_, r, c| (0..3).map(|k| lower.get(r, k) * lower.get(c, k)).sum());
+		assert_eq!(reconstructed.data(), m.data());
+	}
+
+	#[test]
+	fn shuffle_cols()
+	{
+		let original = Matrix::from(2, 5, vec![
+			1., 2., 3., 4., 5.,
+			6., 7., 8., 9., 10.
+		]).unwrap();
+
+		let mut a = original.clone();
+		let mut b = original.clone();
+		a.shuffle_cols(7);
+		b.shuffle_cols(7);
+		assert_eq!(a.data(), b.data());
+
+		// every column keeps its two values together and none are lost
+		let mut cols: Vec<(f64, f64)> = (0..a.cols()).map(|c| (a.get(0, c), a.get(1, c))).collect();
+		cols.sort_by(|x, y| x.partial_cmp(y).unwrap());
+		assert_eq!(cols, vec![(1., 6.), (2., 7.), (3., 8.), (4., 9.), (5., 10.)]);
+	}
+
+	#[test]
+	fn power_iteration()
+	{
+		// dominant eigenvalue 3 with eigenvector (1, 1) / sqrt(2)
+		let m = Matrix::from(2, 2, vec![2., 1., 1., 2.]).unwrap();
+		let v = m.power_iteration(100, 3).unwrap();
+		let expected = 1. / 2_f64.sqrt();
+		assert_eq!(v.rows(), 2);
+		assert_eq!(v.cols(), 1);
+		assert!((v.get(0, 0).abs() - expected).abs() < 1e-9);
+		assert!((v.get(1, 0).abs() - expected).abs() < 1e-9);
+		assert_eq!(v.get(0, 0).signum(), v.get(1, 0).signum());
+	}
+
+	#[test]
+	fn lu_solve_all()
+	{
+		// needs a row swap, since the top-left entry is zero
+		let m = Matrix::from(3, 3, vec![
+			0., 2., 1.,
+			1., 1., 0.,
+			2., 0., 3.
+		]).unwrap();
+		let (det, inv) = m.lu_solve_all().unwrap();
+		assert!((det - (-8.)).abs() < 1e-9);
+
+		let expected = [
+			-0.375, 0.75, 0.125,
+			0.375, 0.25, -0.125,
+			0.25, -0.5, 0.25
+		];
+		for (val, exp) in inv.data().iter().zip(expected.iter()) {
+			assert!((val - exp).abs() < 1e-9);
+		}
+
+		let product = Matrix::mult(&m, &inv).unwrap();
+		for r in 0..3 {
+			for c in 0..3 {
+				let exp = if r == c { 1. } else { 0. };
+				assert!((product.get(r, c) - exp).abs() < 1e-9);
+			}
+		}
+	}
+
+	#[test]
+	fn log_determinant()
+	{
+		let m = Matrix::from(3, 3, vec![
+			2., -1., 0.,
+			-1., 2., -1.,
+			0., -1., -3.
+		]).unwrap();
+		let (det, _) = m.lu_solve_all().unwrap();
+		let (ln_det, sign) = m.log_determinant().unwrap();
+		assert!((sign as f64 * ln_det.exp() - det).abs() < 1e-9);
+		assert_eq!(sign, -1);
+
+		// the determinant of 200 * I overflows, its logarithm doesn't
+		let mut large = Matrix::new(200, 200);
+		large.map(|_, r, c| if r == c { 200. } else { 0. });
+		let (ln_det, sign) = large.log_determinant().unwrap();
+		assert!((ln_det - 200. * 200_f64.ln()).abs() < 1e-9);
+		assert_eq!(sign, 1);
+	}
+
+	#[test]
+	fn pseudo_inverse()
+	{
+		let m = Matrix::from(3, 2, vec![1., 2., 3., 4., 5., 7.]).unwrap();
+		let pinv = m.pseudo_inverse().unwrap();
+		assert_eq!((pinv.rows(), pinv.cols()), (2, 3));
+
+		let product = Matrix::mult(&pinv, &m).unwrap();
+		for row in 0..2 {
+			for col in 0..2 {
+				let exp = if row == col { 1. } else { 0. };
+				assert!((product.get(row, col) - exp).abs() < 1e-9);
+			}
+		}
+	}
+
+	#[test]
+	fn qr()
+	{
+		let m = Matrix::from(3, 2, vec![
+			12., -51.,
+			6., 167.,
+			-4., 24.
+		]).unwrap();
+		let (q, r) = m.qr().unwrap();
+		assert_eq!((q.rows(), q.cols()), (3, 3));
+		assert_eq!((r.rows(), r.cols()), (3, 2));
+
+		let reconstructed = Matrix::mult(&q, &r).unwrap();
+		for (val, exp) in reconstructed.data().iter().zip(m.data().iter()) {
+			assert!((val - exp).abs() < 1e-9);
+		}
+
+		let gram = Matrix::mult(&q.transpose(), &q).unwrap();
+		for row in 0..3 {
+			for col in 0..3 {
+				let exp = if row == col { 1. } else { 0. };
+				assert!((gram.get(row, col) - exp).abs() < 1e-12);
+			}
+		}
+
+		assert!(r.get(1, 0).abs() < 1e-12);
+		assert!(r.get(2, 0).abs() < 1e-12);
+		assert!(r.get(2, 1).abs() < 1e-12);
+	}
+
+	#[test]
+	fn moving_average_rows()
+	{
+		let m = Matrix::from(1, 5, vec![1., 2., 3., 4., 5.]).unwrap();
+		let avg = m.moving_average_rows(3).unwrap();
+		assert_eq!(avg.rows(), 1);
+		assert_eq!(avg.cols(), 3);
+		assert_eq!(avg.data(), vec![2., 3., 4.]);
+	}
+
+	#[test]
+	fn band()
+	{
+		let mut m = Matrix::new(4, 4);
+		m.map(|_, _, _| 1.);
+		let tridiagonal = m.band(1, 1);
+		assert_eq!(tridiagonal.data(), vec![
+			1., 1., 0., 0.,
+			1., 1., 1., 0.,
+			0., 1., 1., 1.,
+			0., 0., 1., 1.
+		]);
+
+		let upper = m.band(0, u32::MAX);
+		assert_eq!(upper.data(), vec![
+			1., 1., 1., 1.,
+			0., 1., 1., 1.,
+			0., 0., 1., 1.,
+			0., 0., 0., 1.
+		]);
+		assert_eq!(m.band(u32::MAX, u32::MAX).data(), m.data());
+	}
+
+	#[test]
+	fn clamp_counting()
+	{
+		let mut m = Matrix::from(2, 3, vec![-5., 0.2, 1.5, 0.9, -0.1, 7.]).unwrap();
+		let count = m.clamp_counting(0., 1.);
+		assert_eq!(count, 4);
+		assert_eq!(m.data(), vec![0., 0.2, 1., 0.9, 0., 1.]);
+	}
+
+	#[test]
+	fn histogram()
+	{
+		// buckets [0, 0.25), [0.25, 0.5), [0.5, 0.75), [0.75, 1]
+		let m = Matrix::from(2, 4, vec![0., 0.25, 0.3, 0.74, 1., -3., 5., f64::NAN]).unwrap();
+		assert_eq!(m.histogram(4, 0., 1.), vec![2, 2, 1, 2]);
+		assert_eq!(m.histogram(1, 0., 1.), vec![7]);
+		assert!(m.histogram(0, 0., 1.).is_empty());
+	}
+
+	#[test]
+	fn sign()
+	{
+		let m = Matrix::from(2, 2, vec![3., -0.5, 0., -7.]).unwrap();
+		assert_eq!(m.sign().data(), vec![1., -1., 0., -1.]);
+		assert_eq!(m.data(), vec![3., -0.5, 0., -7.]);
+	}
+
+	#[test]
+	fn ln()
+	{
+		let m = Matrix::from(2, 2, vec![1., 2., 10., 0.5]).unwrap();
+		let expected = vec![0., 2_f64.ln(), 10_f64.ln(), 0.5_f64.ln()];
+		assert_eq!(m.ln().data(), expected);
+	}
+
+	#[test]
+	fn exp()
+	{
+		let m = Matrix::from(2, 2, vec![1., 2., 10., 0.5]).unwrap();
+		let expected = vec![1_f64.exp(), 2_f64.exp(), 10_f64.exp(), 0.5_f64.exp()];
+		assert_eq!(m.exp().data(), expected);
+	}
+
+	#[test]
+	fn sqrt()
+	{
+		let m = Matrix::from(2, 2, vec![1., 4., 2.25, 0.25]).unwrap();
+		assert_eq!(m.sqrt().data(), vec![1., 2., 1.5, 0.5]);
+	}
+
+	#[test]
+	fn round()
+	{
+		let m = Matrix::from(2, 2, vec![0.25, 2.5, -0.75, 3.49]).unwrap();
+		assert_eq!(m.round().data(), vec![0., 3., -1., 3.]);
+	}
+
+	#[test]
+	fn floor()
+	{
+		let m = Matrix::from(2, 2, vec![0.25, 2.5, -0.75, 3.49]).unwrap();
+		assert_eq!(m.floor().data(), vec![0., 2., -1., 3.]);
+	}
+
+	#[test]
+	fn ceil()
+	{
+		let m = Matrix::from(2, 2, vec![0.25, 2.5, -0.75, 3.49]).unwrap();
+		assert_eq!(m.ceil().data(), vec![1., 3., -0., 4.]);
+	}
+
+	#[test]
+	fn apply_sigmoid()
+	{
+		let m = Matrix::from(1, 3, vec![0., 2., -2.]).unwrap();
+		let s = m.apply_sigmoid();
+		assert_eq!(s.get(0, 0), 0.5);
+		assert!((s.get(0, 1) - 1. / (1. + (-2_f64).exp())).abs() < 1e-12);
+		assert!((s.get(0, 1) + s.get(0, 2) - 1.).abs() < 1e-12);
+		assert_eq!(m.data(), vec![0., 2., -2.]);
+	}
+
+	#[test]
+	fn weighted_sum()
+	{
+		let a = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+		let b = Matrix::from(2, 2, vec![4., 4., 4., 4.]).unwrap();
+		let c = Matrix::from(2, 2, vec![0., 8., 2., 6.]).unwrap();
+		let avg = Matrix::weighted_sum(vec![a, b, c], vec![0.5, 0.25, 0.25]).unwrap();
+		assert_eq!(avg.data(), vec![1.5, 4., 3., 4.5]);
+	}
+
+	#[test]
+	fn weighted_col_mean()
+	{
+		let m = Matrix::from(2, 3, vec![1., 2., 3., 4., 8., 0.]).unwrap();
+		let mean = m.weighted_col_mean(vec![1., 2., 1.]).unwrap();
+		assert_eq!(mean.rows(), 2);
+		assert_eq!(mean.cols(), 1);
+		assert_eq!(mean.data(), vec![2., 5.]);
+	}
+
+	#[test]
+	fn broadcast_to()
+	{
+		let row = Matrix::from(1, 3, vec![1., 2., 3.]).unwrap();
+		assert_eq!(row.broadcast_to(2, 3).unwrap().data(), vec![1., 2., 3., 1., 2., 3.]);
+
+		let col = Matrix::from(2, 1, vec![1., 2.]).unwrap();
+		assert_eq!(col.broadcast_to(2, 3).unwrap().data(), vec![1., 1., 1., 2., 2., 2.]);
+	}
+
+	#[test]
+	fn pairwise_cosine()
+	{
+		let m = Matrix::from(3, 2, vec![1., 0., 1., 1., -2., 0.5]).unwrap();
+		let sim = m.pairwise_cosine();
+		assert_eq!(sim.rows(), 3);
+		assert_eq!(sim.cols(), 3);
+		for a in 0..3 {
+			assert!((sim.get(a, a) - 1.).abs() < 1e-12);
+			for b in 0..3 {
+				assert_eq!(sim.get(a, b), sim.get(b, a));
+			}
+		}
+		assert!((sim.get(0, 1) - 1. / 2_f64.sqrt()).abs() < 1e-12);
+	}
+
+	#[test]
+	fn correlation()
+	{
+		let m = Matrix::from(4, 3, vec![
+			1., 2., 3.,
+			2., 4., 6.,
+			3., 1., 2.,
+			5., 5., 5.,
+		]).unwrap();
+		let corr = m.correlation();
+
+		// centered: x = [-1, 0, 1], z = [1, -1, 0] -> -1 / (√2 · √2)
+		assert!((corr.get(0, 1) - 1.).abs() < 1e-12);
+		assert!((corr.get(0, 2) + 0.5).abs() < 1e-12);
+		assert!((corr.get(2, 1) + 0.5).abs() < 1e-12);
+		for i in 0..4 {
+			assert_eq!(corr.get(i, i), 1.);
+		}
+		for i in 0..3 {
+			assert_eq!(corr.get(3, i), 0.);
+			assert_eq!(corr.get(i, 3), 0.);
+		}
+	}
+
+	#[test]
+	fn row_softmax()
+	{
+		let m = Matrix::from(3, 4, vec![
+			1., 2., 3., 4.,
+			-5., 0., 5., 0.,
+			700., 700., 701., 699.
+		]).unwrap();
+		let soft = m.row_softmax();
+		for row in 0..3 {
+			let sum: f64 = (0..4).map(|col| soft.get(row, col)).sum();
+			assert!((sum - 1.).abs() < 1e-12);
+		}
+		assert!(soft.get(0, 3) > soft.get(0, 2));
+		assert_eq!(soft.get(1, 1), soft.get(1, 3));
+	}
+
+	#[test]
+	fn is_row_stochastic()
+	{
+		let valid = Matrix::from(2, 3, vec![0.2, 0.3, 0.5, 1., 0., 0.]).unwrap();
+		assert!(valid.is_row_stochastic(1e-9));
+		assert!(!valid.is_col_stochastic(1e-9));
+
+		let negative = Matrix::from(1, 3, vec![1.5, -0.5, 0.]).unwrap();
+		assert!(!negative.is_row_stochastic(1e-9));
+	}
+
+	#[test]
+	fn is_col_stochastic()
+	{
+		let valid = Matrix::from(3, 2, vec![0.2, 1., 0.3, 0., 0.5, 0.]).unwrap();
+		assert!(valid.is_col_stochastic(1e-9));
+		assert!(!valid.is_row_stochastic(1e-9));
+	}
+
+	#[test]
+	fn sinkhorn()
+	{
+		let m = Matrix::from(3, 3, vec![1., 5., 0.5, 2., 1., 3., 0.1, 4., 2.]).unwrap();
+		let s = m.sinkhorn(200).unwrap();
+		for i in 0..3 {
+			let row_sum: f64 = (0..3).map(|c| s.get(i, c)).sum();
+			let col_sum: f64 = (0..3).map(|r| s.get(r, i)).sum();
+			assert!((row_sum - 1.).abs() < 1e-9);
+			assert!((col_sum - 1.).abs() < 1e-9);
+		}
+		assert_eq!(m.sinkhorn(0).unwrap().data(), m.data());
+	}
+
+	#[test]
+	fn is_orthogonal()
+	{
+		let (sin, cos) = 0.3_f64.sin_cos();
+		let rotation = Matrix::from(2, 2, vec![cos, -sin, sin, cos]).unwrap();
+		assert!(rotation.is_orthogonal(1e-12));
+
+		let near = Matrix::from(2, 2, vec![cos + 1e-6, -sin, sin, cos]).unwrap();
+		assert!(near.is_orthogonal(1e-5));
+		assert!(!near.is_orthogonal(1e-9));
+
+		let skewed = Matrix::from(2, 2, vec![1., 1., 0., 1.]).unwrap();
+		assert!(!skewed.is_orthogonal(1e-9));
+		assert!(!Matrix::new(2, 3).is_orthogonal(1e-9));
+	}
+
+	#[test]
+	fn argsort_column()
+	{
+		let m = Matrix::from(4, 2, vec![
+			0., 3.,
+			0., -1.,
+			0., 7.,
+			0., 2.
+		]).unwrap();
+		assert_eq!(m.argsort_column(1, false).unwrap(), vec![1, 3, 0, 2]);
+		assert_eq!(m.argsort_column(1, true).unwrap(), vec![2, 0, 3, 1]);
+	}
+
+	#[test]
+	fn from_rows()
+	{
+		let m = Matrix::from_rows(vec![vec![1., 2., 3.], vec![4., 5., 6.]]).unwrap();
+		assert_eq!(m.rows(), 2);
+		assert_eq!(m.cols(), 3);
+		assert_eq!(m.data(), vec![1., 2., 3., 4., 5., 6.]);
+	}
+
+	#[test]
+	fn upscale()
+	{
+		let m = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+		let up = m.upscale(4, 4).unwrap();
+		assert_eq!(up.data(), vec![
+			1., 1., 2., 2.,
+			1., 1., 2., 2.,
+			3., 3., 4., 4.,
+			3., 3., 4., 4.,
+		]);
+
+		// non-integer factors still pick the nearest original item
+		let up = m.upscale(3, 2).unwrap();
+		assert_eq!(up.data(), vec![1., 2., 1., 2., 3., 4.]);
+		assert_eq!(m.upscale(2, 2).unwrap().data(), m.data());
+	}
+
+	#[test]
+	fn to_rows()
+	{
+		let m = Matrix::from(2, 3, vec![1., 2., 3., 4., 5., 6.]).unwrap();
+		assert_eq!(m.to_rows(), vec![vec![1., 2., 3.], vec![4., 5., 6.]]);
+		assert_eq!(Matrix::from_rows(m.to_rows()).unwrap().data(), m.data());
+	}
+
+	#[test]
+	fn rows_json()
+	{
+		let m = Matrix::from_rows_json("[[1, 2, 3], [4, 5, 6]]").unwrap();
+		assert_eq!(m.rows(), 2);
+		assert_eq!(m.cols(), 3);
+		assert_eq!(m.data(), vec![1., 2., 3., 4., 5., 6.]);
+		assert_eq!(Matrix::from_rows_json(&m.to_rows_json()).unwrap().data(), m.data());
+	}
+
+	#[test]
+	fn from_cols()
+	{
+		let m = Matrix::from_cols(vec![vec![1., 4.], vec![2., 5.], vec![3., 6.]]).unwrap();
+		assert_eq!(m.rows(), 2);
+		assert_eq!(m.cols(), 3);
+		assert_eq!(m.data(), vec![1., 2., 3., 4., 5., 6.]);
+	}
+
+	#[test]
+	fn permute_rows()
+	{
+		let m = Matrix::from(3, 2, vec![1., 2., 3., 4., 5., 6.]).unwrap();
+		let permuted = m.permute_rows(vec![2, 0, 1]).unwrap();
+		assert_eq!(permuted.data(), vec![5., 6., 1., 2., 3., 4.]);
+	}
+
+	#[test]
+	fn im2col()
+	{
+		let mut image = Matrix::new(4, 4);
+		image.map(|_, r, c| (r * 4 + c) as f64);
+		let cols = image.im2col(3, 3, 1).unwrap();
+		assert_eq!(cols.rows(), 9);
+		assert_eq!(cols.cols(), 4);
+
+		// the last patch starts at (1, 1)
+		let last: Vec<f64> = (0..9).map(|r| cols.get(r, 3)).collect();
+		assert_eq!(last, vec![5., 6., 7., 9., 10., 11., 13., 14., 15.]);
+	}
+
+	#[test]
+	fn gaussian_blur()
+	{
+		let mut image = Matrix::new(9, 9);
+		image.set(4, 4, 1.);
+		let blurred = image.gaussian_blur(1.);
+
+		assert!(blurred.get(4, 4) < 1.);
+		assert!(blurred.get(4, 5) > 0.);
+		assert!(blurred.get(3, 4) > 0.);
+		assert!(blurred.get(4, 4) > blurred.get(4, 5));
+		assert!(blurred.get(4, 5) > blurred.get(3, 5));
+		assert_eq!(blurred.get(4, 3), blurred.get(4, 5));
+		let total: f64 = blurred.data().iter().sum();
+		assert!((total - 1.).abs() < 1e-9);
+
+		// at the border the mirrored pixel adds to the result
+		let mut edge = Matrix::new(1, 5);
+		edge.set(0, 0, 1.);
+		let total: f64 = edge.gaussian_blur(0.8).data().iter().sum();
+		assert!((total - 1.).abs() < 0.1);
+		assert_eq!(image.gaussian_blur(0.).data(), image.data());
+	}
+
+	#[test]
+	fn from_blocks()
+	{
+		let blocks = vec![
+			vec![Matrix::from(1, 1, vec![1.]).unwrap(), Matrix::from(1, 2, vec![2., 3.]).unwrap()],
+			vec![Matrix::from(1, 1, vec![4.]).unwrap(), Matrix::from(1, 2, vec![5., 6.]).unwrap()],
+		];
+		let m = Matrix::from_blocks(blocks).unwrap();
+		assert_eq!(m.rows(), 2);
+		assert_eq!(m.cols(), 3);
+		assert_eq!(m.data(), vec![1., 2., 3., 4., 5., 6.]);
+	}
+
+	#[test]
+	fn zip_map()
+	{
+		let a = Matrix::from(2, 2, vec![1., 5., -3., 0.]).unwrap();
+		let b = Matrix::from(2, 2, vec![2., 4., -4., 0.]).unwrap();
+		let max = Matrix::zip_map(&a, &b, f64::max).unwrap();
+		assert_eq!(max.data(), vec![2., 5., -3., 0.]);
+
+		let mismatch = Matrix::zip_map(&a, &Matrix::new(1, 4), f64::max);
+		assert_eq!(mismatch.err(), Some(MatrixError::DimensionMismatch));
+	}
+
+	#[test]
+	fn split_rows_at()
+	{
+		let m = Matrix::from(4, 2, vec![1., 2., 3., 4., 5., 6., 7., 8.]).unwrap();
+		let (top, bottom) = m.split_rows_at(3).unwrap();
+		assert_eq!(top.rows(), 3);
+		assert_eq!(top.cols(), 2);
+		assert_eq!(top.data(), vec![1., 2., 3., 4., 5., 6.]);
+		assert_eq!(bottom.rows(), 1);
+		assert_eq!(bottom.data(), vec![7., 8.]);
+	}
+
+	#[test]
+	fn split_cols_at()
+	{
+		let m = Matrix::from(4, 2, vec![1., 2., 3., 4., 5., 6., 7., 8.]).unwrap();
+		let (left, right) = m.split_cols_at(1).unwrap();
+		assert_eq!(left.rows(), 4);
+		assert_eq!(left.cols(), 1);
+		assert_eq!(left.data(), vec![1., 3., 5., 7.]);
+		assert_eq!(right.data(), vec![2., 4., 6., 8.]);
+	}
+
+	// Building a `JsValue` error aborts on native targets, so the failure
+	// paths can only be exercised with `wasm-pack test`.
+	#[cfg(target_arch = "wasm32")]
+	mod wasm
+	{
+		use super::super::Matrix;
+		use wasm_bindgen_test::wasm_bindgen_test;
+
+		#[wasm_bindgen_test]
+		fn add_dimension_mismatch()
+		{
+			let a = Matrix::new(2, 2);
+			let b = Matrix::new(2, 3);
+			assert!(Matrix::add(&a, &b).is_err());
+			assert!(Matrix::sub(&a, &b).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn exp_matrix_not_square()
+		{
+			assert!(Matrix::new(2, 3).exp_matrix(5).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn sinkhorn_negative()
+		{
+			let m = Matrix::from(2, 2, vec![1., -1., 2., 3.]).unwrap();
+			assert!(m.sinkhorn(10).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn try_get_out_of_range()
+		{
+			// (0, 2) would still map to a valid flat index
+			let m = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+			assert!(m.try_get(0, 2).is_err());
+			assert!(m.try_get(2, 0).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn hadamard_dimension_mismatch()
+		{
+			let a = Matrix::new(2, 2);
+			let b = Matrix::new(1, 4);
+			assert!(Matrix::hadamard(&a, &b).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn frobenius_distance_mismatch()
+		{
+			let a = Matrix::new(2, 2);
+			let b = Matrix::new(2, 1);
+			assert!(Matrix::frobenius_distance(&a, &b).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn permute_rows_invalid_index()
+		{
+			let m = Matrix::from(3, 2, vec![1., 2., 3., 4., 5., 6.]).unwrap();
+			assert!(m.permute_rows(vec![0, 3, 1]).is_err());
+			assert!(m.permute_rows(vec![0, 1]).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn upscale_too_small()
+		{
+			let m = Matrix::new(2, 3);
+			assert!(m.upscale(1, 3).is_err());
+			assert!(m.upscale(4, 2).is_err());
+			assert!(Matrix::new(0, 0).upscale(2, 2).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn from_rows_json_invalid()
+		{
+			assert!(Matrix::from_rows_json("[[1, 2], [3]]").is_err());
+			assert!(Matrix::from_rows_json("[]").is_err());
+			assert!(Matrix::from_rows_json("[[1, \"a\"]]").is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn from_blocks_mismatch()
+		{
+			let blocks = vec![
+				vec![Matrix::new(1, 1), Matrix::new(1, 2)],
+				vec![Matrix::new(1, 2), Matrix::new(1, 1)],
+			];
+			assert!(Matrix::from_blocks(blocks).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn broadcast_to_incompatible()
+		{
+			let m = Matrix::from(2, 3, vec![1., 2., 3., 4., 5., 6.]).unwrap();
+			assert!(m.broadcast_to(3, 3).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn cholesky_not_positive_definite()
+		{
+			let m = Matrix::from(2, 2, vec![1., 2., 2., 1.]).unwrap();
+			assert!(m.cholesky().is_err());
+		}
+	}
+}
@@ -1,16 +1,44 @@
-use wasm_bindgen::prelude::wasm_bindgen;
-use crate::Matrix;
+use wasm_bindgen::prelude::{JsValue, wasm_bindgen};
+use std::convert::TryInto;
+use std::mem::size_of;
+use std::ops::Range;
+use rand::{Rng, SeedableRng, rngs::StdRng, thread_rng};
+use serde::{Deserialize, Serialize};
+use crate::{Activation, Init, Matrix};
+
+/// Magic bytes at the start of every buffer created by `export_buffer`.
+const BUFFER_MAGIC: &[u8; 4] = b"NNRS";
+/// Version of the `export_buffer` format.
+const BUFFER_VERSION: u8 = 2;
 
 /// An instance of NeuralNet is able to perform calculations on some
 /// input data. It can be "trained" to give a specific result on some
 /// specific input. It consists of layers of nodes, through which data
 /// "flows".
 #[wasm_bindgen]
+#[derive(Serialize, Deserialize)]
 pub struct NeuralNet {
+	#[serde(default)]
+	input_nodes: u32,
 	hidden_nodes: Vec<u32>,
 	hidden_weights: Vec<Matrix>,
+	biases: Vec<Matrix>,
 	learning_rate: f64,
-	bias: u8,
+	#[serde(default)]
+	activation: Activation,
+	#[serde(default)]
+	gradient_checkpointing: bool,
+	#[serde(default)]
+	output_loss_weights: Vec<f64>,
+}
+
+/// An in-memory copy of the trainable state of a `NeuralNet`. It can
+/// be handed back to `NeuralNet::restore` to roll the net back, e.g.
+/// as a cheap checkpoint inside a training loop.
+#[wasm_bindgen]
+pub struct NeuralNetSnapshot {
+	hidden_weights: Vec<Matrix>,
+	biases: Vec<Matrix>,
 }
 
 #[wasm_bindgen]
@@ -28,8 +56,23 @@ impl NeuralNet
 	/// This `Neural Network` would consist of an input layer with
 	/// `3` nodes, a hidden layer with `2`, one with `3` nodes
 	/// and an output layer with `2` nodes.
+	/// Every node has its own bias. All weights and biases are
+	/// initialized with random values in `[-1, 1]`.
 	#[wasm_bindgen(constructor)]
 	pub fn new(input_nodes: u32, hidden_nodes: Vec<u32>, output_nodes: u32) -> NeuralNet
+	{
+		NeuralNet::new_with_init(input_nodes, hidden_nodes, output_nodes, Init::Uniform)
+	}
+
+	/// Like `new`, but the weights are drawn with the given `Init`
+	/// strategy: from `[-s, s]` where `s` depends on the number of inputs
+	/// of each layer. Biases are still drawn from `[-1, 1]`.
+	/// ```
+	/// use neural_net_rs::{Init, NeuralNet};
+	/// let nn = NeuralNet::new_with_init(100, vec![10], 2, Init::Xavier);
+	/// assert!(nn.weights(0).unwrap().data().iter().all(|w| w.abs() <= 0.1));
+	/// ```
+	pub fn new_with_init(input_nodes: u32, hidden_nodes: Vec<u32>, output_nodes: u32, init: Init) -> NeuralNet
 	{
 		#[cfg(feature = "console_error_panic_hook")]
 		console_error_panic_hook::set_once();
@@ -49,32 +92,2066 @@ impl NeuralNet
 			hidden_weights.push(Matrix::new(output_nodes, input_nodes));
 		}
 
+		let mut biases = Vec::new();
+		for weights in hidden_weights.iter_mut() {
+			weights.randomize();
+			weights.scale(init.scale(weights.cols()));
+			let mut bias = Matrix::new(weights.rows(), 1);
+			bias.randomize();
+			biases.push(bias);
+		}
 
 		NeuralNet {
 			learning_rate: 0.1_f64,
-			bias: 1,
+			biases,
+			input_nodes,
+			activation: Activation::Sigmoid,
+			gradient_checkpointing: false,
+			output_loss_weights: Vec::new(),
 			hidden_nodes,
 			hidden_weights
 		}
 	}
-}
 
-#[cfg(test)]
-mod tests
-{
-	use super::NeuralNet;
+	/// Feeds `input_data` through the net and returns the values of the
+	/// output layer. Fails if `input_data` doesn't match the size of the
+	/// input layer.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let nn = NeuralNet::new(2, vec![3], 1);
+	/// let output = nn.feed_forward(vec![0., 1.]).unwrap();
+	/// assert_eq!(output.len(), 1);
+	/// ```
+	pub fn feed_forward(&self, input_data: Vec<f64>) -> Result<Vec<f64>, JsValue>
+	{
+		self.check_input(&input_data)?;
+		let input = Matrix::from(self.input_nodes, 1, input_data)?;
+		Ok(self.forward(input).pop().unwrap().data())
+	}
 
-	#[test]
-	fn nn_new()
+	/// Sets the activation function used by every layer, both in
+	/// `feed_forward` and in `train`. Defaults to `Activation::Sigmoid`.
+	/// ```
+	/// use neural_net_rs::{Activation, NeuralNet};
+	/// let mut nn = NeuralNet::new(2, vec![3], 1);
+	/// nn.set_activation(Activation::Relu);
+	/// assert!(nn.feed_forward(vec![1., 0.]).unwrap()[0] >= 0.);
+	/// ```
+	pub fn set_activation(&mut self, act: Activation)
 	{
-		let nn = NeuralNet::new(2, vec![3, 4, 5], 2);
-		assert_eq!(nn.hidden_weights.len(), 4);
+		self.activation = act;
 	}
 
-	#[test]
-	fn new_perceptron()
+	/// Returns a copy of the weight matrix of `layer`, with one row per
+	/// node of the layer and one column per node of the previous layer.
+	/// Layer `0` is the first hidden layer (or the output layer of a
+	/// perceptron). Fails if `layer` is out of range.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let nn = NeuralNet::new(2, vec![3], 1);
+	/// assert_eq!(nn.weights(1).unwrap().cols(), 3);
+	/// ```
+	pub fn weights(&self, layer: u32) -> Result<Matrix, JsValue>
 	{
-		let nn = NeuralNet::new(2, Vec::new(), 1);
-		assert_eq!(nn.hidden_weights.len(), 1);
+		self.hidden_weights.get(layer as usize).cloned().ok_or_else(|| JsValue::from_str(&format!(
+			"Error: layer {} out of range for {} layers", layer, self.hidden_weights.len()
+		)))
+	}
+
+	/// Replaces the weight matrix of `layer` with `m`, e.g. to warm-start
+	/// a net with weights trained elsewhere. Fails if `layer` is out of
+	/// range or `m` doesn't have the same dimensions as the current
+	/// weights of that layer.
+	/// ```
+	/// use neural_net_rs::{Matrix, NeuralNet};
+	/// let mut nn = NeuralNet::new(2, vec![3], 1);
+	/// nn.set_weights(1, Matrix::new(1, 3)).unwrap();
+	/// assert_eq!(nn.weights(1).unwrap().data(), vec![0.; 3]);
+	/// ```
+	pub fn set_weights(&mut self, layer: u32, m: Matrix) -> Result<(), JsValue>
+	{
+		let layers = self.hidden_weights.len();
+		let weights = self.hidden_weights.get_mut(layer as usize).ok_or_else(|| JsValue::from_str(&format!(
+			"Error: layer {} out of range for {} layers", layer, layers
+		)))?;
+
+		if weights.rows() != m.rows() || weights.cols() != m.cols() {
+			return Err(JsValue::from_str(&format!(
+				"Error: layer {} has {}x{} weights, got {}x{}",
+				layer, weights.rows(), weights.cols(), m.rows(), m.cols()
+			)));
+		}
+
+		*weights = m;
+		Ok(())
+	}
+
+	/// Returns a copy of the biases of the nodes in `layer` as a column
+	/// vector. Layer `0` is the first hidden layer (or the output layer
+	/// of a perceptron). Fails if `layer` is out of range.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let nn = NeuralNet::new(2, vec![3], 1);
+	/// assert_eq!(nn.bias(0).unwrap().rows(), 3);
+	/// ```
+	pub fn bias(&self, layer: u32) -> Result<Matrix, JsValue>
+	{
+		self.biases.get(layer as usize).cloned().ok_or_else(|| JsValue::from_str(&format!(
+			"Error: layer {} out of range for {} layers", layer, self.biases.len()
+		)))
+	}
+
+	/// Sets the bias of every node in every layer to `bias`.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let mut nn = NeuralNet::new(2, vec![3], 1);
+	/// nn.set_bias(0.5);
+	/// assert_eq!(nn.bias(0).unwrap().data(), vec![0.5; 3]);
+	/// ```
+	pub fn set_bias(&mut self, bias: f64)
+	{
+		for layer in self.biases.iter_mut() {
+			layer.map(|_, _, _| bias);
+		}
+	}
+
+	/// Returns the learning rate used by `train`. Defaults to `0.1`.
+	pub fn learning_rate(&self) -> f64
+	{
+		self.learning_rate
+	}
+
+	/// Sets the learning rate used by `train`. Fails if `rate` is not a
+	/// finite, positive number.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let mut nn = NeuralNet::new(2, vec![3], 1);
+	/// nn.set_learning_rate(0.5).unwrap();
+	/// assert_eq!(nn.learning_rate(), 0.5);
+	/// ```
+	pub fn set_learning_rate(&mut self, rate: f64) -> Result<(), JsValue>
+	{
+		if !rate.is_finite() || rate <= 0. {
+			return Err(JsValue::from_str(&format!("Error: learning rate must be finite and positive, got {}", rate)));
+		}
+		self.learning_rate = rate;
+		Ok(())
+	}
+
+	/// Enables or disables gradient checkpointing in `train`. When
+	/// enabled, only the activations at every `√n`-th of the `n` layers
+	/// are kept during the forward pass, and the others are recomputed
+	/// segment by segment during the backward pass. This trades extra
+	/// computation for less memory on deep nets; the trained weights are
+	/// the same either way. Disabled by default.
+	pub fn set_gradient_checkpointing(&mut self, enabled: bool)
+	{
+		self.gradient_checkpointing = enabled;
+	}
+
+	/// Weights the loss of the output nodes by task, for nets where the
+	/// outputs belong to different tasks. `group_of` assigns every output
+	/// node to a group, and the error of each node is scaled by the
+	/// weight of its group in `group_weights` during training. A weight
+	/// of `0` excludes a group from training. By default all nodes are
+	/// weighted equally. Fails if `group_of` doesn't have one item per
+	/// output node or refers to a group without a weight.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let mut nn = NeuralNet::new(2, vec![3], 3);
+	/// // the first two outputs form one task, the last one another
+	/// nn.set_output_group_weights(vec![0, 0, 1], vec![1., 0.5]).unwrap();
+	/// ```
+	pub fn set_output_group_weights(&mut self, group_of: Vec<u32>, group_weights: Vec<f64>) -> Result<(), JsValue>
+	{
+		let output_nodes = self.hidden_weights[self.hidden_weights.len() - 1].rows();
+		if group_of.len() != output_nodes as usize {
+			return Err(JsValue::from_str(&format!("Error: expected a group for each of the {} outputs, got {}", output_nodes, group_of.len())));
+		}
+		if let Some(group) = group_of.iter().find(|group| **group as usize >= group_weights.len()) {
+			return Err(JsValue::from_str(&format!("Error: group {} has no weight, only {} weights given", group, group_weights.len())));
+		}
+
+		self.output_loss_weights = group_of.iter().map(|group| group_weights[*group as usize]).collect();
+		Ok(())
+	}
+
+	/// Trains the net on a single example with backpropagation. The
+	/// weights are moved by `learning_rate` in the direction that brings
+	/// the output of `input` closer to `target`. Fails if `input` doesn't
+	/// match the size of the input layer or `target` doesn't match the
+	/// size of the output layer.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let mut nn = NeuralNet::new(2, vec![3], 1);
+	/// nn.train(vec![0., 1.], vec![1.]).unwrap();
+	/// ```
+	pub fn train(&mut self, input: Vec<f64>, target: Vec<f64>) -> Result<(), JsValue>
+	{
+		let output_nodes = self.hidden_weights[self.hidden_weights.len() - 1].rows();
+
+		self.check_input(&input)?;
+		if target.len() != output_nodes as usize {
+			return Err(JsValue::from_str(&format!("Error: expected {} targets, got {}", output_nodes, target.len())));
+		}
+
+		let input = Matrix::from(self.input_nodes, 1, input)?;
+		let target = Matrix::from(output_nodes, 1, target)?;
+
+		let layers = self.hidden_weights.len();
+		let stride = if self.gradient_checkpointing {
+			(layers as f64).sqrt().ceil() as usize
+		} else {
+			layers
+		};
+
+		// only the inputs of every segment of `stride` layers are kept
+		let mut checkpoints = vec![input];
+		for start in (0..layers).step_by(stride).skip(1) {
+			let mut segment = self.forward_range(checkpoints[checkpoints.len() - 1].clone(), start - stride..start);
+			checkpoints.push(segment.pop().unwrap());
+		}
+
+		let mut error = None;
+		for (idx, checkpoint) in checkpoints.into_iter().enumerate().rev() {
+			let start = idx * stride;
+			let activations = self.forward_range(checkpoint, start..(start + stride).min(layers));
+			let segment_error = match error.take() {
+				Some(error) => error,
+				None => self.output_error(&target, &activations[activations.len() - 1])?,
+			};
+
+			let (deltas, segment_error) = self.backward(start, &activations, segment_error)?;
+			self.apply_deltas(start, &deltas)?;
+			error = Some(segment_error);
+		}
+
+		Ok(())
+	}
+
+	/// Trains the net as one branch of a siamese network: both inputs
+	/// are fed through the net, and the outputs are treated as their
+	/// embeddings. For a `similar` pair the weights are moved to pull the
+	/// embeddings together, otherwise to push them at least `margin`
+	/// apart (pairs that are already further apart are left alone). This
+	/// follows the gradient of the contrastive loss `d² / 2` for similar
+	/// and `max(0, margin - d)² / 2` for dissimilar pairs, where `d` is
+	/// the Euclidean distance of the embeddings. Fails if an input
+	/// doesn't match the size of the input layer.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let mut nn = NeuralNet::new(2, vec![3], 2);
+	/// nn.train_contrastive(vec![0., 1.], vec![1., 0.], false, 1.).unwrap();
+	/// ```
+	pub fn train_contrastive(&mut self, input_a: Vec<f64>, input_b: Vec<f64>, similar: bool, margin: f64) -> Result<(), JsValue>
+	{
+		self.check_input(&input_a)?;
+		self.check_input(&input_b)?;
+
+		let activations_a = self.forward(Matrix::from(self.input_nodes, 1, input_a)?);
+		let activations_b = self.forward(Matrix::from(self.input_nodes, 1, input_b)?);
+		let diff = Matrix::sub(&activations_a[activations_a.len() - 1], &activations_b[activations_b.len() - 1])?;
+		let distance = diff.data().iter().map(|val| val * val).sum::<f64>().sqrt();
+
+		// the error is the negative gradient of the loss w.r.t. embedding a
+		let mut error_a = diff;
+		if similar {
+			error_a.scale(-1_f64);
+		} else if distance < margin && distance > 0_f64 {
+			error_a.scale((margin - distance) / distance);
+		} else {
+			return Ok(());
+		}
+		let mut error_b = error_a.clone();
+		error_b.scale(-1_f64);
+
+		let (deltas_a, _) = self.backward(0, &activations_a, error_a)?;
+		let (deltas_b, _) = self.backward(0, &activations_b, error_b)?;
+		let mut deltas = Vec::new();
+		for ((weights_a, bias_a), (weights_b, bias_b)) in deltas_a.iter().zip(deltas_b.iter()) {
+			deltas.push((Matrix::add(weights_a, weights_b)?, Matrix::add(bias_a, bias_b)?));
+		}
+		self.apply_deltas(0, &deltas)
+	}
+
+	/// Trains the net as a denoising autoencoder: Gaussian noise with a
+	/// standard deviation of `noise_level` is added to `clean_input`, and
+	/// the net is trained to reconstruct the clean input from the noisy
+	/// one. The noise is drawn from a RNG seeded with `seed`. Only
+	/// training is affected, `feed_forward` stays noise-free. Fails if the
+	/// output layer is not the same size as the input layer.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let mut nn = NeuralNet::new(3, vec![2], 3);
+	/// nn.train_denoising(vec![0.2, 0.5, 0.8], 0.1, 42).unwrap();
+	/// ```
+	pub fn train_denoising(&mut self, clean_input: Vec<f64>, noise_level: f64, seed: u64) -> Result<(), JsValue>
+	{
+		let noisy_input = add_gaussian_noise(&clean_input, noise_level, seed);
+		self.train(noisy_input, clean_input)
+	}
+
+	/// Trains the net on a whole dataset for `epochs` epochs, calling
+	/// `train` once per sample. `inputs` and `targets` hold all
+	/// `sample_count` samples flattened one after another, so a single
+	/// call replaces a loop over `train` in JS. The order of the samples
+	/// is shuffled in every epoch. Fails if `sample_count` is `0` or the
+	/// lengths don't match `sample_count` times the size of the input
+	/// and output layer respectively.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let mut nn = NeuralNet::new(2, vec![3], 1);
+	/// nn.fit(vec![0., 1., 1., 0.], vec![1., 1.], 2, 10).unwrap();
+	/// ```
+	pub fn fit(&mut self, inputs: Vec<f64>, targets: Vec<f64>, sample_count: u32, epochs: u32) -> Result<(), JsValue>
+	{
+		self.check_dataset(&inputs, &targets, sample_count)?;
+
+		let mut rng = thread_rng();
+		let mut order: Vec<usize> = (0..sample_count as usize).collect();
+		for _ in 0..epochs {
+			for i in (1..order.len()).rev() {
+				let j = rng.gen_range(0..=i);
+				order.swap(i, j);
+			}
+			self.train_samples(&inputs, &targets, &order)?;
+		}
+
+		Ok(())
+	}
+
+	/// Like `fit`, but first holds out `val_fraction` of the samples
+	/// (rounded down) as a validation set, which is never trained on.
+	/// The split and the order of the training samples in every epoch
+	/// are drawn from a RNG seeded with `seed`. Returns the mean squared
+	/// error on the validation set after every epoch, or `NaN` for every
+	/// epoch if the validation set is empty. Fails like `fit`, or if
+	/// `val_fraction` is not in `[0, 1)`.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let mut nn = NeuralNet::new(2, vec![3], 1);
+	/// let inputs = vec![0., 0., 0., 1., 1., 0., 1., 1.];
+	/// let losses = nn.train_split(inputs, vec![0., 1., 1., 0.], 4, 5, 0.25, 42).unwrap();
+	/// assert_eq!(losses.len(), 5);
+	/// ```
+	pub fn train_split(
+		&mut self, inputs: Vec<f64>, targets: Vec<f64>, sample_count: u32,
+		epochs: u32, val_fraction: f64, seed: u64
+	) -> Result<Vec<f64>, JsValue>
+	{
+		self.check_dataset(&inputs, &targets, sample_count)?;
+		if !(0_f64..1_f64).contains(&val_fraction) {
+			return Err(JsValue::from_str("Error: validation fraction must be in [0, 1)"));
+		}
+
+		let input_nodes = self.input_nodes as usize;
+		let output_nodes = self.hidden_weights[self.hidden_weights.len() - 1].rows() as usize;
+		let mut rng = StdRng::seed_from_u64(seed);
+		let (mut train, validation) = split_samples(sample_count as usize, val_fraction, &mut rng);
+
+		let mut losses = Vec::new();
+		for _ in 0..epochs {
+			for i in (1..train.len()).rev() {
+				let j = rng.gen_range(0..=i);
+				train.swap(i, j);
+			}
+			self.train_samples(&inputs, &targets, &train)?;
+
+			let mut loss = 0_f64;
+			for sample in validation.iter() {
+				let input = inputs[sample * input_nodes..(sample + 1) * input_nodes].to_vec();
+				let target = targets[sample * output_nodes..(sample + 1) * output_nodes].to_vec();
+				loss += self.mse(input, target)?;
+			}
+			losses.push(loss / validation.len() as f64);
+		}
+
+		Ok(losses)
+	}
+
+	/// Runs a learning rate range test to help picking a learning rate:
+	/// over `steps` steps the learning rate grows exponentially from
+	/// `min_lr` to `max_lr`, and in every step the net is trained on the
+	/// next of the `sample_count` samples (flattened like in `fit`) and
+	/// the mean squared error over all samples is recorded. The losses
+	/// are returned smoothed with an exponential moving average, where
+	/// `smoothing` is the weight of the previous average, so `0` returns
+	/// the raw losses. Plotting them against the learning rates shows
+	/// where the loss drops fastest. Weights, biases and learning rate are
+	/// restored afterwards. Fails if the learning rates are not positive
+	/// or the dataset is invalid like in `fit`.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let mut nn = NeuralNet::new(2, vec![3], 1);
+	/// let losses = nn.lr_finder(vec![0., 1., 1., 0.], vec![1., 1.], 2, 1e-3, 1., 10, 0.9).unwrap();
+	/// assert_eq!(losses.len(), 10);
+	/// ```
+	#[allow(clippy::too_many_arguments)]
+	pub fn lr_finder(
+		&mut self, inputs: Vec<f64>, targets: Vec<f64>, sample_count: u32,
+		min_lr: f64, max_lr: f64, steps: u32, smoothing: f64
+	) -> Result<Vec<f64>, JsValue>
+	{
+		let snap = self.snapshot();
+		let learning_rate = self.learning_rate;
+		let losses = self.lr_range_test(&inputs, &targets, sample_count, min_lr, max_lr, steps);
+		self.restore(&snap)?;
+		self.learning_rate = learning_rate;
+
+		let mut average = None;
+		Ok(losses?.into_iter().map(|loss| {
+			let smoothed = match average {
+				Some(average) => smoothing * average + (1_f64 - smoothing) * loss,
+				None => loss,
+			};
+			average = Some(smoothed);
+			smoothed
+		}).collect())
+	}
+
+	/// Feeds `input` through the net and returns the mean squared error
+	/// of the output compared to `target`. Fails if `input` doesn't match
+	/// the size of the input layer or `target` doesn't match the size of
+	/// the output layer.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let nn = NeuralNet::new(2, vec![3], 1);
+	/// assert!(nn.mse(vec![0., 1.], vec![1.]).unwrap() <= 1.);
+	/// ```
+	pub fn mse(&self, input: Vec<f64>, target: Vec<f64>) -> Result<f64, JsValue>
+	{
+		let output_nodes = self.hidden_weights[self.hidden_weights.len() - 1].rows();
+
+		if target.len() != output_nodes as usize {
+			return Err(JsValue::from_str(&format!("Error: expected {} targets, got {}", output_nodes, target.len())));
+		}
+
+		let output = self.feed_forward(input)?;
+		let sum: f64 = output.iter().zip(target.iter()).map(|(o, t)| (o - t).powi(2)).sum();
+		Ok(sum / output.len() as f64)
+	}
+
+	/// Feeds `input_data` through the net like `feed_forward`, but applies
+	/// a softmax instead of the activation function on the output layer,
+	/// so the outputs form a probability distribution that sums to `1`.
+	/// The maximum is subtracted before exponentiating to avoid overflow.
+	/// Fails if `input_data` doesn't match the size of the input layer.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let nn = NeuralNet::new(2, vec![3], 4);
+	/// let probabilities = nn.feed_forward_softmax(vec![0.5, 0.1]).unwrap();
+	/// assert!((probabilities.iter().sum::<f64>() - 1.).abs() < 1e-9);
+	/// ```
+	pub fn feed_forward_softmax(&self, input_data: Vec<f64>) -> Result<Vec<f64>, JsValue>
+	{
+		self.check_input(&input_data)?;
+		let input = Matrix::from(self.input_nodes, 1, input_data)?;
+		Ok(self.softmax_output(input))
+	}
+
+	/// Feeds `input` through the net and returns the index of the output
+	/// node with the highest value, i.e. the predicted class. If several
+	/// outputs share the highest value, the lowest index is returned.
+	/// Fails if `input` doesn't match the size of the input layer.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let nn = NeuralNet::new(2, vec![3], 4);
+	/// assert!(nn.predict_label(vec![0.5, 0.1]).unwrap() < 4);
+	/// ```
+	pub fn predict_label(&self, input: Vec<f64>) -> Result<u32, JsValue>
+	{
+		let output = self.feed_forward(input)?;
+		let mut best = 0;
+		for (idx, val) in output.iter().enumerate() {
+			if *val > output[best] {
+				best = idx;
+			}
+		}
+		Ok(best as u32)
+	}
+
+	/// Returns the recall of every class: the fraction of the samples
+	/// labelled with a class that `predict_label` assigns to that class.
+	/// `inputs` holds `sample_count` inputs back to back and `labels` one
+	/// class index per sample. Classes without any samples get `NaN`.
+	/// Fails if the lengths don't match `sample_count` or a label is not
+	/// below `num_classes`.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let nn = NeuralNet::new(2, vec![3], 2);
+	/// let recall = nn.per_class_accuracy(vec![0.5, 0.1, 0.2, 0.9], vec![0, 1], 2, 2).unwrap();
+	/// assert_eq!(recall.len(), 2);
+	/// ```
+	pub fn per_class_accuracy(&self, inputs: Vec<f64>, labels: Vec<u32>, sample_count: u32, num_classes: u32) -> Result<Vec<f64>, JsValue>
+	{
+		let input_nodes = self.input_nodes as usize;
+		let samples = sample_count as usize;
+		if inputs.len() != samples * input_nodes {
+			return Err(JsValue::from_str(&format!(
+				"Error: expected {} inputs for {} samples, got {}", samples * input_nodes, samples, inputs.len()
+			)));
+		}
+		if labels.len() != samples {
+			return Err(JsValue::from_str(&format!(
+				"Error: expected {} labels for {} samples, got {}", samples, samples, labels.len()
+			)));
+		}
+		if let Some(label) = labels.iter().find(|label| **label >= num_classes) {
+			return Err(JsValue::from_str(&format!(
+				"Error: label {} out of range for {} classes", label, num_classes
+			)));
+		}
+
+		let mut correct = vec![0_u32; num_classes as usize];
+		let mut total = vec![0_u32; num_classes as usize];
+		for (input, label) in inputs.chunks(input_nodes.max(1)).zip(labels.iter()) {
+			total[*label as usize] += 1;
+			if self.predict_label(input.to_vec())? == *label {
+				correct[*label as usize] += 1;
+			}
+		}
+
+		Ok(correct.iter().zip(total.iter()).map(|(c, t)| {
+			if *t == 0 { f64::NAN } else { *c as f64 / *t as f64 }
+		}).collect())
+	}
+
+	/// Returns the `k` most likely classes for `input_data` together with
+	/// their softmax probabilities, most likely first. The result is a
+	/// flat vector of pairs `[index, score, index, score, ...]`, with the
+	/// class indices encoded as floats so it can be passed to JS as one
+	/// array. If `k` exceeds the number of outputs, all classes are
+	/// returned. Equal scores keep the lower index first. Fails if
+	/// `input_data` doesn't match the size of the input layer.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let nn = NeuralNet::new(2, vec![3], 4);
+	/// let top = nn.top_k_scored(vec![0.5, 0.1], 2).unwrap();
+	/// assert_eq!(top.len(), 4);
+	/// assert!(top[1] >= top[3]);
+	/// ```
+	pub fn top_k_scored(&self, input_data: Vec<f64>, k: u32) -> Result<Vec<f64>, JsValue>
+	{
+		let probabilities = self.feed_forward_softmax(input_data)?;
+
+		let column = Matrix::from(probabilities.len() as u32, 1, probabilities.clone())?;
+		let ranking = column.argsort_column(0, true)?;
+		Ok(ranking.iter()
+			.take(k as usize)
+			.flat_map(|idx| vec![*idx as f64, probabilities[*idx as usize]])
+			.collect())
+	}
+
+	/// Creates an adversarial example with the fast gradient sign method:
+	/// every item of `input_data` is moved by `epsilon` in the direction
+	/// that increases the output of the `target_class` node, based on
+	/// the sign of the gradient of that output w.r.t. the input. The
+	/// result is clamped to `[0, 1]`, the usual range of normalized
+	/// inputs. Fails if `input_data` doesn't match the size of the input
+	/// layer or `target_class` is not an output node.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let nn = NeuralNet::new(2, vec![3], 2);
+	/// let adversarial = nn.fgsm(vec![0.5, 0.5], 1, 0.1).unwrap();
+	/// assert!(adversarial.iter().all(|v| (v - 0.5).abs() <= 0.1));
+	/// ```
+	pub fn fgsm(&self, input_data: Vec<f64>, target_class: u32, epsilon: f64) -> Result<Vec<f64>, JsValue>
+	{
+		let output_nodes = self.hidden_weights[self.hidden_weights.len() - 1].rows();
+
+		self.check_input(&input_data)?;
+		if target_class >= output_nodes {
+			return Err(JsValue::from_str(&format!("Error: target class {} out of range for {} outputs", target_class, output_nodes)));
+		}
+
+		let activations = self.forward(Matrix::from(self.input_nodes, 1, input_data.clone())?);
+		let mut gradient = Matrix::new(output_nodes, 1);
+		gradient.set(target_class, 0, 1_f64);
+
+		for layer in (0..self.hidden_weights.len()).rev() {
+			let mut derivative = activations[layer + 1].clone();
+			derivative.map(|val, _, _| self.activation.derivative(val));
+			gradient = Matrix::hadamard(&derivative, &gradient)?;
+			gradient = Matrix::mult(&self.hidden_weights[layer].transpose(), &gradient)?;
+		}
+
+		Ok(input_data.iter().zip(gradient.sign().data())
+			.map(|(val, sign)| (val + epsilon * sign).clamp(0_f64, 1_f64))
+			.collect())
+	}
+
+	/// Serializes the full state of the net (layer sizes, weights,
+	/// biases and learning rate) into a JSON string, e.g. to store it in
+	/// `localStorage`. Use `from_json` to load it again.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let nn = NeuralNet::new(2, vec![3], 1);
+	/// let json = nn.to_json();
+	/// let loaded = NeuralNet::from_json(&json).unwrap();
+	/// assert_eq!(loaded.feed_forward(vec![1., 0.]).unwrap(), nn.feed_forward(vec![1., 0.]).unwrap());
+	/// ```
+	pub fn to_json(&self) -> String
+	{
+		serde_json::to_string(self).unwrap()
+	}
+
+	/// Loads a net from a JSON string created by `to_json`. Fails if the
+	/// string is not valid JSON or doesn't describe a consistent net.
+	/// The size of the input layer is taken from the first weight matrix
+	/// if it wasn't saved.
+	pub fn from_json(json: &str) -> Result<NeuralNet, JsValue>
+	{
+		let mut nn: NeuralNet = serde_json::from_str(json)
+			.map_err(|e| JsValue::from_str(&format!("Error: could not parse net: {}", e)))?;
+
+		if nn.input_nodes == 0 {
+			nn.input_nodes = nn.hidden_weights.first().map_or(0, |m| m.cols());
+		}
+
+		if nn.hidden_weights.iter().chain(nn.biases.iter()).any(|m| m.data().len() != (m.rows() * m.cols()) as usize) {
+			return Err(JsValue::from_str("Error: length of weights does not match `rows` x `cols`"));
+		}
+		nn.validate()?;
+
+		Ok(nn)
+	}
+
+	/// Serializes the full state of the net into a compact binary buffer,
+	/// e.g. to transfer it to a web worker as an `ArrayBuffer`. Use
+	/// `import_buffer` to load it again. All numbers are little-endian:
+	///
+	/// | offset   | content                                              |
+	/// |----------|------------------------------------------------------|
+	/// | `0`      | magic bytes `NNRS`                                   |
+	/// | `4`      | format version (`u8`), activation (`u8`), gradient checkpointing (`u8`), padding |
+	/// | `8`      | learning rate (`f64`)                                |
+	/// | `16`     | number of weight matrices `n` (`u32`)                |
+	/// | `20`     | `n` pairs of rows and columns (`u32`)                |
+	/// | aligned  | all weights (`f64`), row by row, one matrix after another |
+	/// | ...      | all biases (`f64`), one layer after another          |
+	///
+	/// The weights start at the next multiple of `8` after the header and
+	/// are directly followed by the biases, so all parameters form one
+	/// block that can be read in place, e.g. as a `Float64Array`. Output
+	/// group weights are not included.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let nn = NeuralNet::new(2, vec![3], 1);
+	/// let loaded = NeuralNet::import_buffer(&nn.export_buffer()).unwrap();
+	/// assert_eq!(loaded.feed_forward(vec![1., 0.]).unwrap(), nn.feed_forward(vec![1., 0.]).unwrap());
+	/// ```
+	pub fn export_buffer(&self) -> Vec<u8>
+	{
+		let mut buffer = BUFFER_MAGIC.to_vec();
+		buffer.extend_from_slice(&[BUFFER_VERSION, self.activation as u8, self.gradient_checkpointing as u8, 0]);
+		buffer.extend_from_slice(&self.learning_rate.to_le_bytes());
+		buffer.extend_from_slice(&(self.hidden_weights.len() as u32).to_le_bytes());
+		for weights in self.hidden_weights.iter() {
+			buffer.extend_from_slice(&weights.rows().to_le_bytes());
+			buffer.extend_from_slice(&weights.cols().to_le_bytes());
+		}
+
+		buffer.resize(align_to_f64(buffer.len()), 0);
+		for matrix in self.hidden_weights.iter().chain(self.biases.iter()) {
+			for val in matrix.data() {
+				buffer.extend_from_slice(&val.to_le_bytes());
+			}
+		}
+		buffer
+	}
+
+	/// Loads a net from a buffer created by `export_buffer`. Fails if the
+	/// buffer is truncated, has trailing bytes, was created by an
+	/// unsupported format version or doesn't describe a consistent net.
+	pub fn import_buffer(buffer: &[u8]) -> Result<NeuralNet, JsValue>
+	{
+		let malformed = || JsValue::from_str("Error: malformed net buffer");
+
+		if buffer.get(0..4) != Some(&BUFFER_MAGIC[..]) {
+			return Err(malformed());
+		}
+		if buffer.get(4) != Some(&BUFFER_VERSION) {
+			return Err(JsValue::from_str("Error: unsupported net buffer version"));
+		}
+
+		let activation = match buffer.get(5) {
+			Some(0) => Activation::Sigmoid,
+			Some(1) => Activation::Tanh,
+			Some(2) => Activation::Relu,
+			_ => return Err(malformed()),
+		};
+		let gradient_checkpointing = buffer.get(6) == Some(&1);
+		let learning_rate = read_f64(buffer, 8).ok_or_else(malformed)?;
+		let layers = read_u32(buffer, 16).ok_or_else(malformed)?;
+
+		let mut shapes = Vec::new();
+		let mut offset = 20;
+		for _ in 0..layers {
+			let rows = read_u32(buffer, offset).ok_or_else(malformed)?;
+			let cols = read_u32(buffer, offset + 4).ok_or_else(malformed)?;
+			shapes.push((rows, cols));
+			offset += 8;
+		}
+		let bias_shapes: Vec<(u32, u32)> = shapes.iter().map(|(rows, _)| (*rows, 1)).collect();
+
+		offset = align_to_f64(offset);
+		let mut matrices = Vec::new();
+		for (rows, cols) in shapes.into_iter().chain(bias_shapes) {
+			let len = (rows as usize).checked_mul(cols as usize).ok_or_else(malformed)?;
+			if len > buffer.len().saturating_sub(offset) / size_of::<f64>() {
+				return Err(malformed());
+			}
+			let data = (0..len).map(|i| read_f64(buffer, offset + i * size_of::<f64>()).unwrap()).collect();
+			matrices.push(Matrix::from(rows, cols, data)?);
+			offset += len * size_of::<f64>();
+		}
+		if offset != buffer.len() || matrices.is_empty() {
+			return Err(malformed());
+		}
+
+		let biases = matrices.split_off(matrices.len() / 2);
+		let hidden_weights = matrices;
+		let nn = NeuralNet {
+			input_nodes: hidden_weights[0].cols(),
+			hidden_nodes: hidden_weights[..hidden_weights.len() - 1].iter().map(|m| m.rows()).collect(),
+			hidden_weights,
+			biases,
+			learning_rate,
+			activation,
+			gradient_checkpointing,
+			output_loss_weights: Vec::new(),
+		};
+		nn.validate()?;
+
+		Ok(nn)
+	}
+
+	/// Returns the approximate amount of memory in bytes the net
+	/// occupies: `8` bytes for every weight and bias, plus the struct
+	/// itself, the headers of its matrices and the stored layer sizes.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let small = NeuralNet::new(2, vec![2], 1);
+	/// let large = NeuralNet::new(2, vec![20], 1);
+	/// assert!(large.memory_bytes() > small.memory_bytes());
+	/// ```
+	pub fn memory_bytes(&self) -> usize
+	{
+		let parameters: usize = self.hidden_weights.iter().chain(self.biases.iter())
+			.map(|m| (m.rows() * m.cols()) as usize)
+			.sum();
+
+		size_of::<NeuralNet>()
+			+ (self.hidden_weights.len() + self.biases.len()) * size_of::<Matrix>()
+			+ self.hidden_nodes.len() * size_of::<u32>()
+			+ parameters * size_of::<f64>()
+	}
+
+	/// Captures the current weights and biases of the net.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let mut nn = NeuralNet::new(2, vec![3], 1);
+	/// let snap = nn.snapshot();
+	/// nn.restore(&snap).unwrap();
+	/// ```
+	pub fn snapshot(&self) -> NeuralNetSnapshot
+	{
+		NeuralNetSnapshot {
+			hidden_weights: self.hidden_weights.clone(),
+			biases: self.biases.clone(),
+		}
+	}
+
+	/// Restores weights and biases from a snapshot. Fails if the snapshot
+	/// was taken from a net with a different topology.
+	pub fn restore(&mut self, snap: &NeuralNetSnapshot) -> Result<(), JsValue>
+	{
+		let compatible = snap.hidden_weights.len() == self.hidden_weights.len()
+			&& snap.hidden_weights.iter().zip(self.hidden_weights.iter())
+				.all(|(a, b)| a.rows() == b.rows() && a.cols() == b.cols());
+
+		if !compatible {
+			return Err(JsValue::from_str("Error: snapshot does not match the topology of this net"));
+		}
+
+		self.hidden_weights = snap.hidden_weights.clone();
+		self.biases = snap.biases.clone();
+		Ok(())
+	}
+
+	/// Averages the weights and biases of several nets with identical
+	/// topologies, e.g. to aggregate nets trained on different data. The
+	/// learning rate is taken from the first net.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let a = NeuralNet::new(2, vec![3], 1);
+	/// let b = NeuralNet::new(2, vec![3], 1);
+	/// let avg = NeuralNet::average(vec![a, b]).unwrap();
+	/// ```
+	pub fn average(nets: Vec<NeuralNet>) -> Result<NeuralNet, JsValue>
+	{
+		if nets.is_empty() {
+			return Err(JsValue::from_str("Error: cannot average an empty list of nets"));
+		}
+
+		let first = &nets[0];
+		let same_topology = nets.iter().all(|nn| {
+			nn.input_nodes == first.input_nodes
+				&& nn.hidden_nodes == first.hidden_nodes
+				&& nn.hidden_weights.len() == first.hidden_weights.len()
+				&& nn.hidden_weights.iter().zip(first.hidden_weights.iter())
+					.all(|(a, b)| a.rows() == b.rows() && a.cols() == b.cols())
+		});
+		if !same_topology {
+			return Err(JsValue::from_str("Error: all nets must have the same topology"));
+		}
+
+		let factors = vec![1_f64 / nets.len() as f64; nets.len()];
+		let mut hidden_weights = Vec::new();
+		let mut biases = Vec::new();
+		for layer in 0..first.hidden_weights.len() {
+			let matrices = nets.iter().map(|nn| nn.hidden_weights[layer].clone()).collect();
+			hidden_weights.push(Matrix::weighted_sum(matrices, factors.clone())?);
+			let matrices = nets.iter().map(|nn| nn.biases[layer].clone()).collect();
+			biases.push(Matrix::weighted_sum(matrices, factors.clone())?);
+		}
+
+		Ok(NeuralNet {
+			learning_rate: first.learning_rate,
+			biases,
+			activation: first.activation,
+			gradient_checkpointing: first.gradient_checkpointing,
+			output_loss_weights: first.output_loss_weights.clone(),
+			input_nodes: first.input_nodes,
+			hidden_nodes: first.hidden_nodes.clone(),
+			hidden_weights
+		})
+	}
+
+	/// Assigns each of `sample_count` samples to one of `k` folds for
+	/// cross-validation. The samples are shuffled with a seeded RNG and
+	/// dealt out round-robin, so fold sizes differ by at most one and the
+	/// same `seed` always gives the same assignment. Returns an empty
+	/// vector if `k` is `0`.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let folds = NeuralNet::k_fold_indices(10, 3, 1);
+	/// assert_eq!(folds.len(), 10);
+	/// assert!(folds.iter().all(|f| *f < 3));
+	/// ```
+	pub fn k_fold_indices(sample_count: u32, k: u32, seed: u64) -> Vec<u32>
+	{
+		if k == 0 {
+			return Vec::new();
+		}
+
+		let mut rng = StdRng::seed_from_u64(seed);
+		let mut order: Vec<u32> = (0..sample_count).collect();
+		for i in (1..order.len()).rev() {
+			let j = rng.gen_range(0..=i);
+			order.swap(i, j);
+		}
+
+		let mut folds = vec![0; sample_count as usize];
+		for (position, sample) in order.iter().enumerate() {
+			folds[*sample as usize] = position as u32 % k;
+		}
+		folds
+	}
+
+	/// Returns the number of nodes in every layer: the input layer, the
+	/// hidden layers and the output layer.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let nn = NeuralNet::new(3, vec![2, 4], 2);
+	/// assert_eq!(nn.shape(), vec![3, 2, 4, 2]);
+	/// ```
+	pub fn shape(&self) -> Vec<u32>
+	{
+		let mut shape = vec![self.input_nodes];
+		shape.extend_from_slice(&self.hidden_nodes);
+		shape.push(self.hidden_weights[self.hidden_weights.len() - 1].rows());
+		shape
+	}
+
+	/// Checks that the weight matrices form a consistent chain: the first
+	/// layer must have one input per node of the input layer, every
+	/// other layer as many inputs as the previous layer has outputs, the
+	/// hidden layers must match the stored layer sizes, and every layer
+	/// needs a bias column vector with one item per node. Returns the
+	/// first inconsistency found.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let nn = NeuralNet::new(3, vec![2, 4], 2);
+	/// assert!(nn.validate().is_ok());
+	/// ```
+	pub fn validate(&self) -> Result<(), JsValue>
+	{
+		if self.hidden_weights.len() != self.hidden_nodes.len() + 1 {
+			return Err(JsValue::from_str(&format!(
+				"Error: expected {} weight matrices for {} hidden layers, found {}",
+				self.hidden_nodes.len() + 1, self.hidden_nodes.len(), self.hidden_weights.len()
+			)));
+		}
+
+		for (i, nodes) in self.hidden_nodes.iter().enumerate() {
+			if self.hidden_weights[i].rows() != *nodes {
+				return Err(JsValue::from_str(&format!(
+					"Error: layer {} has {} outputs, but hidden layer {} has {} nodes",
+					i, self.hidden_weights[i].rows(), i, nodes
+				)));
+			}
+		}
+
+		if self.hidden_weights[0].cols() != self.input_nodes {
+			return Err(JsValue::from_str(&format!(
+				"Error: layer 0 has {} inputs, but the input layer has {} nodes",
+				self.hidden_weights[0].cols(), self.input_nodes
+			)));
+		}
+
+		for i in 1..self.hidden_weights.len() {
+			let prev = &self.hidden_weights[i - 1];
+			let curr = &self.hidden_weights[i];
+			if prev.rows() != curr.cols() {
+				return Err(JsValue::from_str(&format!(
+					"Error: layer {} has {} inputs, but layer {} has {} outputs",
+					i, curr.cols(), i - 1, prev.rows()
+				)));
+			}
+		}
+
+		let output_nodes = self.hidden_weights[self.hidden_weights.len() - 1].rows();
+		if !self.output_loss_weights.is_empty() && self.output_loss_weights.len() != output_nodes as usize {
+			return Err(JsValue::from_str(&format!(
+				"Error: expected {} output loss weights, found {}", output_nodes, self.output_loss_weights.len()
+			)));
+		}
+
+		if self.biases.len() != self.hidden_weights.len() {
+			return Err(JsValue::from_str(&format!(
+				"Error: expected {} bias vectors, found {}", self.hidden_weights.len(), self.biases.len()
+			)));
+		}
+		for (i, (weights, bias)) in self.hidden_weights.iter().zip(self.biases.iter()).enumerate() {
+			if bias.rows() != weights.rows() || bias.cols() != 1 {
+				return Err(JsValue::from_str(&format!(
+					"Error: layer {} has {} outputs, but its biases are {}x{}",
+					i, weights.rows(), bias.rows(), bias.cols()
+				)));
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Blends the current weights and biases with freshly drawn random
+	/// values in `[-1, 1]`: `new = (1 - factor) * current + factor *
+	/// random`. A `factor` of `0` leaves the net unchanged, `1` replaces
+	/// all weights and biases. The random values are drawn from a RNG
+	/// seeded with `seed`.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let mut nn = NeuralNet::new(2, vec![3], 1);
+	/// nn.soft_reset(0.5, 42);
+	/// ```
+	pub fn soft_reset(&mut self, factor: f64, seed: u64)
+	{
+		let mut rng = StdRng::seed_from_u64(seed);
+		for weights in self.hidden_weights.iter_mut().chain(self.biases.iter_mut()) {
+			weights.map(|val, _, _| {
+				let random = rng.gen_range(-1_f64..=1_f64);
+				(1_f64 - factor) * val + factor * random
+			});
+		}
+	}
+
+	/// Returns a new net with the same input and output layer but the
+	/// hidden layers given in `new_hidden`. Layers whose dimensions are
+	/// the same as the layer at the same position in this net keep their
+	/// weights and biases, all other layers are filled with random values
+	/// in `[-1, 1]` drawn from a RNG seeded with `seed`. The learning
+	/// rate, activation and other settings are copied over.
+	/// ```
+	/// use neural_net_rs::NeuralNet;
+	/// let nn = NeuralNet::new(3, vec![4], 2);
+	/// let wider = nn.reshape_topology(vec![4, 6], 42);
+	/// assert_eq!(wider.shape(), vec![3, 4, 6, 2]);
+	/// ```
+	pub fn reshape_topology(&self, new_hidden: Vec<u32>, seed: u64) -> NeuralNet
+	{
+		let mut rng = StdRng::seed_from_u64(seed);
+		let mut shape = vec![self.input_nodes];
+		shape.extend_from_slice(&new_hidden);
+		shape.push(self.hidden_weights[self.hidden_weights.len() - 1].rows());
+
+		let mut hidden_weights = Vec::new();
+		let mut biases = Vec::new();
+		for (i, dims) in shape.windows(2).enumerate() {
+			let (rows, cols) = (dims[1], dims[0]);
+			match self.hidden_weights.get(i) {
+				Some(weights) if weights.rows() == rows && weights.cols() == cols => {
+					hidden_weights.push(weights.clone());
+					biases.push(self.biases[i].clone());
+				},
+				_ => {
+					hidden_weights.push(Matrix::random(rows, cols, rng.gen()));
+					biases.push(Matrix::random(rows, 1, rng.gen()));
+				}
+			}
+		}
+
+		NeuralNet {
+			learning_rate: self.learning_rate,
+			biases,
+			input_nodes: self.input_nodes,
+			activation: self.activation,
+			gradient_checkpointing: self.gradient_checkpointing,
+			output_loss_weights: self.output_loss_weights.clone(),
+			hidden_nodes: new_hidden,
+			hidden_weights
+		}
+	}
+}
+
+/// Methods in this `impl` are **not** accessable from JavaScript.
+impl NeuralNet
+{
+	/// Feeds `input` through the net and returns the activations of
+	/// every layer, starting with the input itself and ending with the
+	/// output layer.
+	fn forward(&self, input: Matrix) -> Vec<Matrix>
+	{
+		self.forward_range(input, 0..self.hidden_weights.len())
+	}
+
+	/// Feeds `input` through the given range of layers only, where
+	/// `input` is the activation of the layer before the range. Returns
+	/// `input` followed by the activations of every layer in the range.
+	fn forward_range(&self, input: Matrix, layers: Range<usize>) -> Vec<Matrix>
+	{
+		let mut activations = vec![input];
+		for layer in layers {
+			let mut output = self.weighted_input(layer, &activations[activations.len() - 1]);
+			output.map(|v, _, _| self.activation.apply(v));
+			activations.push(output);
+		}
+		activations
+	}
+
+	/// Checks that `input` has one value per node of the input layer.
+	fn check_input(&self, input: &[f64]) -> Result<(), JsValue>
+	{
+		if input.len() != self.input_nodes as usize {
+			return Err(JsValue::from_str(&format!("Error: expected {} inputs, got {}", self.input_nodes, input.len())));
+		}
+		Ok(())
+	}
+
+	/// Checks that `inputs` and `targets` hold `sample_count` flattened
+	/// samples that fit the input and output layer.
+	fn check_dataset(&self, inputs: &[f64], targets: &[f64], sample_count: u32) -> Result<(), JsValue>
+	{
+		let input_nodes = self.input_nodes as usize;
+		let output_nodes = self.hidden_weights[self.hidden_weights.len() - 1].rows() as usize;
+		let samples = sample_count as usize;
+
+		if samples == 0 {
+			return Err(JsValue::from_str("Error: sample count must be greater than 0"));
+		}
+		if inputs.len() != samples * input_nodes {
+			return Err(JsValue::from_str(&format!(
+				"Error: expected {} inputs for {} samples, got {}", samples * input_nodes, samples, inputs.len()
+			)));
+		}
+		if targets.len() != samples * output_nodes {
+			return Err(JsValue::from_str(&format!(
+				"Error: expected {} targets for {} samples, got {}", samples * output_nodes, samples, targets.len()
+			)));
+		}
+		Ok(())
+	}
+
+	/// The raw sweep behind `lr_finder`: trains the net with growing
+	/// learning rates and returns the unsmoothed loss after every step.
+	/// Leaves the net in its trained state.
+	fn lr_range_test(
+		&mut self, inputs: &[f64], targets: &[f64], sample_count: u32,
+		min_lr: f64, max_lr: f64, steps: u32
+	) -> Result<Vec<f64>, JsValue>
+	{
+		self.check_dataset(inputs, targets, sample_count)?;
+		if !(min_lr > 0_f64 && max_lr > 0_f64 && min_lr.is_finite() && max_lr.is_finite()) {
+			return Err(JsValue::from_str("Error: learning rates must be finite and positive"));
+		}
+
+		let input_nodes = self.input_nodes as usize;
+		let output_nodes = self.hidden_weights[self.hidden_weights.len() - 1].rows() as usize;
+		let samples = sample_count as usize;
+		let input = |i: usize| inputs[i * input_nodes..(i + 1) * input_nodes].to_vec();
+		let target = |i: usize| targets[i * output_nodes..(i + 1) * output_nodes].to_vec();
+
+		let mut losses = Vec::new();
+		for step in 0..steps as usize {
+			let progress = if steps > 1 { step as f64 / (steps - 1) as f64 } else { 0_f64 };
+			self.learning_rate = min_lr * (max_lr / min_lr).powf(progress);
+			self.train(input(step % samples), target(step % samples))?;
+
+			let mut loss = 0_f64;
+			for i in 0..samples {
+				loss += self.mse(input(i), target(i))?;
+			}
+			losses.push(loss / samples as f64);
+		}
+		Ok(losses)
+	}
+
+	/// Calls `train` once for every sample index in `order`, in that
+	/// order. `inputs` and `targets` hold all samples flattened like in
+	/// `fit`.
+	fn train_samples(&mut self, inputs: &[f64], targets: &[f64], order: &[usize]) -> Result<(), JsValue>
+	{
+		let input_nodes = self.input_nodes as usize;
+		let output_nodes = self.hidden_weights[self.hidden_weights.len() - 1].rows() as usize;
+		for sample in order.iter() {
+			let input = inputs[sample * input_nodes..(sample + 1) * input_nodes].to_vec();
+			let target = targets[sample * output_nodes..(sample + 1) * output_nodes].to_vec();
+			self.train(input, target)?;
+		}
+		Ok(())
+	}
+
+	/// Returns the error of the output layer, i.e. the target minus the
+	/// actual output, with the error of every node scaled by the weight
+	/// of its output group.
+	fn output_error(&self, target: &Matrix, output: &Matrix) -> Result<Matrix, JsValue>
+	{
+		let mut error = Matrix::sub(target, output)?;
+		if !self.output_loss_weights.is_empty() {
+			error.map(|val, row, _| val * self.output_loss_weights[row as usize]);
+		}
+		Ok(error)
+	}
+
+	/// Backpropagates `error` (the target minus the actual output) through
+	/// the layers starting at `start`, whose activations are given by
+	/// `activations` as returned by `forward_range`. Returns the weight
+	/// and bias deltas of these layers, already scaled by the learning
+	/// rate, and the error at the input of the first of them, i.e. the
+	/// negative gradient of the loss w.r.t. the activations feeding into
+	/// that layer. The net itself is not changed.
+	fn backward(&self, start: usize, activations: &[Matrix], mut error: Matrix) -> Result<(Vec<(Matrix, Matrix)>, Matrix), JsValue>
+	{
+		let mut deltas = Vec::new();
+		for layer in (start..start + activations.len() - 1).rev() {
+			let mut gradient = activations[layer - start + 1].clone();
+			gradient.map(|val, _, _| self.activation.derivative(val));
+			gradient = Matrix::hadamard(&gradient, &error)?;
+			error = Matrix::mult(&self.hidden_weights[layer].transpose(), &gradient)?;
+			gradient.scale(self.learning_rate);
+
+			let weights_delta = Matrix::mult(&gradient, &activations[layer - start].transpose())?;
+			deltas.push((weights_delta, gradient));
+		}
+		deltas.reverse();
+		Ok((deltas, error))
+	}
+
+	/// Adds the weight and bias deltas returned by `backward` to the
+	/// layers starting at `start`.
+	fn apply_deltas(&mut self, start: usize, deltas: &[(Matrix, Matrix)]) -> Result<(), JsValue>
+	{
+		for (offset, (weights_delta, bias_delta)) in deltas.iter().enumerate() {
+			let layer = start + offset;
+			self.hidden_weights[layer] = Matrix::add(&self.hidden_weights[layer], weights_delta)?;
+			self.biases[layer] = Matrix::add(&self.biases[layer], bias_delta)?;
+		}
+		Ok(())
+	}
+
+	/// Computes the input of the nodes in `layer` before the activation
+	/// function is applied: the weighted sum of `input` plus the biases.
+	fn weighted_input(&self, layer: usize, input: &Matrix) -> Matrix
+	{
+		let output = Matrix::mult(&self.hidden_weights[layer], input).unwrap();
+		Matrix::add(&output, &self.biases[layer]).unwrap()
+	}
+
+	/// Feeds `input` through the net, but replaces the activation of the
+	/// output layer with a softmax, so the outputs form a probability
+	/// distribution.
+	fn softmax_output(&self, input: Matrix) -> Vec<f64>
+	{
+		let last = self.hidden_weights.len() - 1;
+		let activations = self.forward_range(input, 0..last);
+		let logits = self.weighted_input(last, &activations[last]);
+		logits.transpose().row_softmax().data()
+	}
+}
+
+/// Shuffles the indices `0..sample_count` with `rng` and splits them
+/// into a training and a validation set, where the validation set gets
+/// `val_fraction` of the samples, rounded down.
+fn split_samples(sample_count: usize, val_fraction: f64, rng: &mut impl Rng) -> (Vec<usize>, Vec<usize>)
+{
+	let mut order: Vec<usize> = (0..sample_count).collect();
+	for i in (1..order.len()).rev() {
+		let j = rng.gen_range(0..=i);
+		order.swap(i, j);
+	}
+	let validation = order.split_off(sample_count - (sample_count as f64 * val_fraction) as usize);
+	(order, validation)
+}
+
+/// Returns a copy of `values` with Gaussian noise of standard deviation
+/// `std_dev` added to every item, drawn with the Box-Muller transform
+/// from a RNG seeded with `seed`.
+fn add_gaussian_noise(values: &[f64], std_dev: f64, seed: u64) -> Vec<f64>
+{
+	let mut rng = StdRng::seed_from_u64(seed);
+	values.iter().map(|val| {
+		let u1 = 1_f64 - rng.gen::<f64>();
+		let u2 = rng.gen::<f64>();
+		let z = (-2_f64 * u1.ln()).sqrt() * (2_f64 * std::f64::consts::PI * u2).cos();
+		val + std_dev * z
+	}).collect()
+}
+
+/// Rounds `offset` up to the next multiple of the size of an `f64`.
+fn align_to_f64(offset: usize) -> usize
+{
+	offset.div_ceil(size_of::<f64>()) * size_of::<f64>()
+}
+
+/// Reads a little-endian `u32` at `offset`, if the buffer is long enough.
+fn read_u32(buffer: &[u8], offset: usize) -> Option<u32>
+{
+	let bytes = buffer.get(offset..offset + 4)?;
+	Some(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Reads a little-endian `f64` at `offset`, if the buffer is long enough.
+fn read_f64(buffer: &[u8], offset: usize) -> Option<f64>
+{
+	let bytes = buffer.get(offset..offset + 8)?;
+	Some(f64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::{NeuralNet, split_samples};
+	use crate::{Activation, Init, Matrix};
+	use rand::{SeedableRng, rngs::StdRng};
+	use std::mem::size_of;
+
+	/// The trainable matrices of `nn`, in a fixed order.
+	fn params(nn: &mut NeuralNet) -> Vec<&mut Matrix>
+	{
+		nn.hidden_weights.iter_mut().chain(nn.biases.iter_mut()).collect()
+	}
+
+	/// Runs `step` on `nn` and asserts that it moved every trainable
+	/// parameter by one step of gradient descent on `loss`, with the
+	/// gradient estimated by central finite differences.
+	fn assert_gradient_step(nn: &mut NeuralNet, step: impl FnOnce(&mut NeuralNet), loss: impl Fn(&NeuralNet) -> f64)
+	{
+		let h = 1e-5;
+		let learning_rate = nn.learning_rate;
+		let before: Vec<Matrix> = params(nn).into_iter().map(|m| m.clone()).collect();
+
+		let mut expected = Vec::new();
+		for (idx, m) in before.iter().enumerate() {
+			let mut data = m.data();
+			for i in 0..data.len() {
+				let val = data[i];
+				data[i] = val + h;
+				*params(nn)[idx] = Matrix::from(m.rows(), m.cols(), data.clone()).unwrap();
+				let up = loss(nn);
+				data[i] = val - h;
+				*params(nn)[idx] = Matrix::from(m.rows(), m.cols(), data.clone()).unwrap();
+				let down = loss(nn);
+				data[i] = val;
+				expected.push(-learning_rate * (up - down) / (2. * h));
+			}
+			*params(nn)[idx] = m.clone();
+		}
+
+		step(nn);
+		let old = before.iter().flat_map(|m| m.data());
+		let new: Vec<f64> = params(nn).iter().flat_map(|m| m.data()).collect();
+		for (i, ((old, new), expected)) in old.zip(new.iter()).zip(expected.iter()).enumerate() {
+			let actual = new - old;
+			assert!(
+				(actual - expected).abs() < 1e-8 + 1e-5 * expected.abs(),
+				"parameter {}: step {} but expected {}", i, actual, expected
+			);
+		}
+	}
+
+	#[test]
+	fn nn_new()
+	{
+		let nn = NeuralNet::new(2, vec![3, 4, 5], 2);
+		assert_eq!(nn.hidden_weights.len(), 4);
+	}
+
+	#[test]
+	fn new_perceptron()
+	{
+		let nn = NeuralNet::new(2, Vec::new(), 1);
+		assert_eq!(nn.hidden_weights.len(), 1);
+	}
+
+	#[test]
+	fn random_weights()
+	{
+		let a = NeuralNet::new(3, vec![4], 2);
+		let b = NeuralNet::new(3, vec![4], 2);
+		assert_ne!(a.hidden_weights[0].data(), b.hidden_weights[0].data());
+		assert_ne!(a.hidden_weights[1].data(), b.hidden_weights[1].data());
+	}
+
+	#[test]
+	fn feed_forward()
+	{
+		let mut nn = NeuralNet::new(2, Vec::new(), 1);
+		nn.hidden_weights[0] = Matrix::from(1, 2, vec![0.5, -1.]).unwrap();
+		nn.set_bias(1.);
+		let output = nn.feed_forward(vec![2., 1.]).unwrap();
+		// sigmoid(0.5 * 2 - 1 * 1 + bias)
+		assert_eq!(output, vec![1. / (1. + (-1_f64).exp())]);
+		assert_eq!(nn.input_nodes, 2);
+	}
+
+	#[test]
+	fn set_activation()
+	{
+		let mut sigmoid = NeuralNet::new(2, Vec::new(), 2);
+		sigmoid.hidden_weights[0] = Matrix::from(2, 2, vec![0.5, 1., -3., -1.]).unwrap();
+		sigmoid.set_bias(1.);
+		let mut tanh = NeuralNet::from_json(&sigmoid.to_json()).unwrap();
+		tanh.set_activation(Activation::Tanh);
+		let mut relu = NeuralNet::from_json(&sigmoid.to_json()).unwrap();
+		relu.set_activation(Activation::Relu);
+
+		let input = vec![1., 1.];
+		let sigmoid_out = sigmoid.feed_forward(input.clone()).unwrap();
+		let tanh_out = tanh.feed_forward(input.clone()).unwrap();
+		let relu_out = relu.feed_forward(input).unwrap();
+		assert_ne!(sigmoid_out, tanh_out);
+		assert_ne!(sigmoid_out, relu_out);
+		assert_ne!(tanh_out, relu_out);
+
+		// the second node gets -3 - 1 + bias = -3, which ReLU clamps to 0
+		assert_eq!(relu_out, vec![2.5, 0.]);
+	}
+
+	#[test]
+	fn per_class_accuracy()
+	{
+		// the net always predicts the larger of its two inputs
+		let mut nn = NeuralNet::new(2, Vec::new(), 2);
+		nn.hidden_weights[0] = Matrix::from(2, 2, vec![1., 0., 0., 1.]).unwrap();
+		nn.set_bias(0.);
+
+		// class 0: 2 of 3 right, class 1: 1 of 1 right, class 2: no samples
+		let inputs = vec![0.9, 0.1, 0.8, 0.3, 0.2, 0.7, 0.1, 0.6];
+		let labels = vec![0, 0, 0, 1];
+		let recall = nn.per_class_accuracy(inputs, labels, 4, 3).unwrap();
+		assert!((recall[0] - 2. / 3.).abs() < 1e-12);
+		assert_eq!(recall[1], 1.);
+		assert!(recall[2].is_nan());
+	}
+
+	#[test]
+	fn new_with_init()
+	{
+		let variance = |nn: &NeuralNet| {
+			let data = nn.hidden_weights[0].data();
+			data.iter().map(|w| w * w).sum::<f64>() / data.len() as f64
+		};
+
+		// uniform on [-s, s] has a variance of s² / 3
+		let narrow = NeuralNet::new_with_init(4, vec![50], 1, Init::Xavier);
+		let wide = NeuralNet::new_with_init(400, vec![50], 1, Init::Xavier);
+		assert!((variance(&narrow) - 1. / 12.).abs() < 0.03);
+		assert!((variance(&wide) - 1. / 1200.).abs() < 3e-4);
+		assert!(variance(&wide) * 10. < variance(&narrow));
+
+		let he = NeuralNet::new_with_init(8, vec![3], 2, Init::He);
+		assert!(he.hidden_weights[0].data().iter().all(|w| w.abs() <= 0.5));
+		assert!(he.validate().is_ok());
+	}
+
+	#[test]
+	fn train_split()
+	{
+		let mut rng = StdRng::seed_from_u64(3);
+		let (mut train, mut validation) = split_samples(10, 0.3, &mut rng);
+		assert_eq!(train.len(), 7);
+		assert_eq!(validation.len(), 3);
+		train.append(&mut validation);
+		train.sort_unstable();
+		assert_eq!(train, (0..10).collect::<Vec<usize>>());
+
+		let (train, validation) = split_samples(4, 0., &mut rng);
+		assert_eq!((train.len(), validation.len()), (4, 0));
+
+		let mut nn = NeuralNet::new(2, vec![3], 1);
+		let inputs = vec![0., 0., 0., 1., 1., 0., 1., 1., 0.5, 0.5];
+		let targets = vec![0., 1., 1., 0., 0.5];
+		let losses = nn.train_split(inputs.clone(), targets.clone(), 5, 7, 0.4, 1).unwrap();
+		assert_eq!(losses.len(), 7);
+		assert!(losses.iter().all(|loss| loss.is_finite() && *loss >= 0.));
+
+		let losses = nn.train_split(inputs, targets, 5, 2, 0., 1).unwrap();
+		assert!(losses.iter().all(|loss| loss.is_nan()));
+	}
+
+	#[test]
+	fn weights()
+	{
+		let mut nn = NeuralNet::new(2, vec![3], 1);
+		assert_eq!(nn.weights(0).unwrap().data(), nn.hidden_weights[0].data());
+
+		let m = Matrix::from(3, 2, vec![1., 2., 3., 4., 5., 6.]).unwrap();
+		nn.set_weights(0, m).unwrap();
+		assert_eq!(nn.weights(0).unwrap().data(), vec![1., 2., 3., 4., 5., 6.]);
+		assert!(nn.validate().is_ok());
+	}
+
+	#[test]
+	fn bias()
+	{
+		let nn = NeuralNet::new(3, vec![2, 4], 2);
+		let shapes: Vec<(u32, u32)> = nn.biases.iter().map(|b| (b.rows(), b.cols())).collect();
+		assert_eq!(shapes, vec![(2, 1), (4, 1), (2, 1)]);
+		assert_eq!(nn.bias(1).unwrap().data(), nn.biases[1].data());
+
+		let mut nn = NeuralNet::new(2, Vec::new(), 2);
+		nn.hidden_weights[0] = Matrix::from(2, 2, vec![0.5, -1., 0.5, -1.]).unwrap();
+		let sigmoid = |x: f64| 1. / (1. + (-x).exp());
+
+		// 0.5 * 2 - 1 * 1 = 0 before the bias is added
+		nn.set_bias(0.5);
+		assert_eq!(nn.feed_forward(vec![2., 1.]).unwrap(), vec![sigmoid(0.5), sigmoid(0.5)]);
+		nn.biases[0] = Matrix::from(2, 1, vec![1., -2.]).unwrap();
+		assert_eq!(nn.feed_forward(vec![2., 1.]).unwrap(), vec![sigmoid(1.), sigmoid(-2.)]);
+	}
+
+	#[test]
+	fn learning_rate()
+	{
+		let mut nn = NeuralNet::new(2, vec![3], 1);
+		assert_eq!(nn.learning_rate(), 0.1);
+		nn.set_learning_rate(0.02).unwrap();
+		assert_eq!(nn.learning_rate(), 0.02);
+	}
+
+	#[test]
+	fn train()
+	{
+		let mut nn = NeuralNet::new(2, vec![3], 2);
+		let input = vec![0.3, 0.8];
+		let target = vec![0.9, 0.1];
+		let error = |nn: &NeuralNet| -> f64 {
+			nn.feed_forward(input.clone()).unwrap().iter().zip(target.iter())
+				.map(|(o, t)| (o - t).powi(2))
+				.sum()
+		};
+
+		let before = error(&nn);
+		for _ in 0..500 {
+			nn.train(input.clone(), target.clone()).unwrap();
+		}
+		let after = error(&nn);
+		assert!(after < before);
+		assert!(after < 1e-2);
+	}
+
+	#[test]
+	fn train_follows_gradient()
+	{
+		let input = vec![0.3, 0.8, -0.5];
+		let target = vec![0.9, 0.1];
+		let loss = |nn: &NeuralNet| -> f64 {
+			nn.feed_forward(input.clone()).unwrap().iter().zip(target.iter())
+				.map(|(o, t)| (t - o).powi(2) / 2.)
+				.sum()
+		};
+
+		for act in [Activation::Sigmoid, Activation::Tanh, Activation::Relu].iter() {
+			let mut nn = NeuralNet::new(3, vec![4, 3], 2);
+			nn.set_activation(*act);
+			assert_gradient_step(&mut nn, |nn| nn.train(input.clone(), target.clone()).unwrap(), loss);
+		}
+	}
+
+	#[test]
+	fn gradient_checkpointing()
+	{
+		// 4 layers of weights, so the backward pass runs in two segments
+		let mut plain = NeuralNet::new(3, vec![4, 3, 5], 2);
+		let mut checkpointed = NeuralNet::from_json(&plain.to_json()).unwrap();
+		checkpointed.set_gradient_checkpointing(true);
+
+		for _ in 0..3 {
+			plain.train(vec![0.1, 0.7, 0.4], vec![1., 0.]).unwrap();
+			checkpointed.train(vec![0.1, 0.7, 0.4], vec![1., 0.]).unwrap();
+		}
+
+		let params = |nn: &NeuralNet| -> Vec<f64> {
+			nn.hidden_weights.iter().chain(nn.biases.iter()).flat_map(|m| m.data()).collect()
+		};
+		for (x, y) in params(&plain).iter().zip(params(&checkpointed).iter()) {
+			assert!((x - y).abs() < 1e-12);
+		}
+	}
+
+	#[test]
+	fn fit()
+	{
+		let inputs = vec![0., 0., 0., 1., 1., 0., 1., 1.];
+		let targets = vec![0., 1., 1., 0.];
+		let error = |nn: &NeuralNet| -> f64 {
+			(0..4).map(|i| (nn.feed_forward(inputs[2 * i..2 * i + 2].to_vec()).unwrap()[0] - targets[i]).powi(2)).sum()
+		};
+
+		let mut nn = NeuralNet::new(2, vec![4], 1);
+		let before = error(&nn);
+		nn.fit(inputs.clone(), targets.clone(), 4, 200).unwrap();
+		assert!(error(&nn) < before);
+	}
+
+	#[test]
+	fn set_output_group_weights()
+	{
+		// in a perceptron every output node only has its own weights
+		let mut nn = NeuralNet::new(2, Vec::new(), 2);
+		nn.set_output_group_weights(vec![0, 1], vec![1., 0.]).unwrap();
+		let before = nn.hidden_weights[0].clone();
+		nn.train(vec![0.4, 0.9], vec![1., 0.]).unwrap();
+		assert_ne!(nn.hidden_weights[0].get(0, 0), before.get(0, 0));
+		assert_eq!(nn.hidden_weights[0].get(1, 0), before.get(1, 0));
+		assert_eq!(nn.hidden_weights[0].get(1, 1), before.get(1, 1));
+
+		// if only the zero-weighted node is off, nothing is learned at all
+		let mut nn = NeuralNet::new(2, vec![3], 2);
+		nn.set_output_group_weights(vec![0, 1], vec![0., 2.]).unwrap();
+		let output = nn.feed_forward(vec![0.4, 0.9]).unwrap();
+		let before = nn.to_json();
+		nn.train(vec![0.4, 0.9], vec![1. - output[0], output[1]]).unwrap();
+		assert_eq!(nn.to_json(), before);
+	}
+
+	#[test]
+	fn train_contrastive()
+	{
+		let mut nn = NeuralNet::new(3, vec![4], 3);
+		let a = vec![0.9, 0.1, 0.4];
+		let b = vec![0.2, 0.8, 0.5];
+		let distance = |nn: &NeuralNet| -> f64 {
+			nn.feed_forward(a.clone()).unwrap().iter().zip(nn.feed_forward(b.clone()).unwrap().iter())
+				.map(|(x, y)| (x - y).powi(2))
+				.sum::<f64>()
+				.sqrt()
+		};
+
+		let before = distance(&nn);
+		for _ in 0..50 {
+			nn.train_contrastive(a.clone(), b.clone(), true, 1.).unwrap();
+		}
+		let pulled = distance(&nn);
+		assert!(pulled < before);
+
+		for _ in 0..50 {
+			nn.train_contrastive(a.clone(), b.clone(), false, 1.).unwrap();
+		}
+		assert!(distance(&nn) > pulled);
+	}
+
+	#[test]
+	fn train_contrastive_follows_gradient()
+	{
+		let a = vec![0.9, 0.1, 0.4];
+		let b = vec![0.2, 0.8, 0.5];
+		let distance = |nn: &NeuralNet| -> f64 {
+			nn.feed_forward(a.clone()).unwrap().iter().zip(nn.feed_forward(b.clone()).unwrap().iter())
+				.map(|(x, y)| (x - y).powi(2))
+				.sum::<f64>()
+				.sqrt()
+		};
+
+		let mut nn = NeuralNet::new(3, vec![4, 3], 2);
+		let step = |nn: &mut NeuralNet| nn.train_contrastive(a.clone(), b.clone(), true, 1.).unwrap();
+		assert_gradient_step(&mut nn, step, |nn| distance(nn).powi(2) / 2.);
+
+		// a margin beyond the current distance keeps the hinge active
+		let mut nn = NeuralNet::new(3, vec![4, 3], 2);
+		nn.set_activation(Activation::Tanh);
+		let margin = distance(&nn) + 1.;
+		let step = |nn: &mut NeuralNet| nn.train_contrastive(a.clone(), b.clone(), false, margin).unwrap();
+		assert_gradient_step(&mut nn, step, |nn| (margin - distance(nn)).max(0.).powi(2) / 2.);
+	}
+
+	#[test]
+	fn train_denoising()
+	{
+		let clean = vec![0.2, 0.5, 0.8];
+		let noisy = super::add_gaussian_noise(&clean, 0.1, 7);
+		assert_eq!(noisy, super::add_gaussian_noise(&clean, 0.1, 7));
+		assert_ne!(noisy, clean);
+
+		let mut nn = NeuralNet::new(3, vec![4], 3);
+		let error = |nn: &NeuralNet| -> f64 {
+			nn.feed_forward(clean.clone()).unwrap().iter().zip(clean.iter())
+				.map(|(o, t)| (o - t).powi(2))
+				.sum()
+		};
+
+		let before = error(&nn);
+		for step in 0..500 {
+			nn.train_denoising(clean.clone(), 0.05, step).unwrap();
+		}
+		assert!(error(&nn) < before);
+	}
+
+	#[test]
+	fn lr_finder()
+	{
+		let inputs = vec![0., 0., 0., 1., 1., 0., 1., 1.];
+		let targets = vec![0., 1., 1., 0.];
+		let mut nn = NeuralNet::new(2, vec![3], 1);
+		let before = nn.to_json();
+
+		let mut sweep = NeuralNet::from_json(&before).unwrap();
+		let raw = sweep.lr_range_test(&inputs, &targets, 4, 1e-3, 10., 20).unwrap();
+
+		let unsmoothed = nn.lr_finder(inputs.clone(), targets.clone(), 4, 1e-3, 10., 20, 0.).unwrap();
+		assert_eq!(unsmoothed, raw);
+		assert_eq!(nn.to_json(), before);
+
+		let smoothed = nn.lr_finder(inputs, targets, 4, 1e-3, 10., 20, 0.5).unwrap();
+		assert_eq!(smoothed[0], raw[0]);
+		assert!((smoothed[1] - (raw[0] + raw[1]) / 2.).abs() < 1e-12);
+		assert_eq!(nn.to_json(), before);
+	}
+
+	#[test]
+	fn mse()
+	{
+		// with zero weights every output is sigmoid(bias)
+		let mut nn = NeuralNet::new(2, Vec::new(), 2);
+		nn.hidden_weights[0] = Matrix::new(2, 2);
+		nn.set_bias(1.);
+		let out = 1. / (1. + (-1_f64).exp());
+
+		let expected = (out.powi(2) + (1. - out).powi(2)) / 2.;
+		assert!((nn.mse(vec![0.3, 0.7], vec![0., 1.]).unwrap() - expected).abs() < 1e-12);
+		assert_eq!(nn.mse(vec![0.3, 0.7], vec![out, out]).unwrap(), 0.);
+	}
+
+	#[test]
+	fn feed_forward_softmax()
+	{
+		// with identity weights the logits are the inputs plus the bias
+		let mut nn = NeuralNet::new(3, Vec::new(), 3);
+		nn.hidden_weights[0] = Matrix::from(3, 3, vec![1., 0., 0., 0., 1., 0., 0., 0., 1.]).unwrap();
+		nn.set_bias(1.);
+
+		let probabilities = nn.feed_forward_softmax(vec![0.2, 700., -3.]).unwrap();
+		assert!((probabilities.iter().sum::<f64>() - 1.).abs() < 1e-9);
+		assert!(probabilities[1] > probabilities[0]);
+		assert!(probabilities[0] > probabilities[2]);
+
+		// the logits can be recovered from the sigmoid outputs
+		let nn = NeuralNet::new(2, vec![4, 3], 3);
+		let logits: Vec<f64> = nn.feed_forward(vec![0.3, 0.6]).unwrap().iter().map(|o| (o / (1. - o)).ln()).collect();
+		let sum: f64 = logits.iter().map(|l| l.exp()).sum();
+		let probabilities = nn.feed_forward_softmax(vec![0.3, 0.6]).unwrap();
+		for (p, l) in probabilities.iter().zip(logits.iter()) {
+			assert!((p - l.exp() / sum).abs() < 1e-9);
+		}
+	}
+
+	#[test]
+	fn predict_label()
+	{
+		let nn = NeuralNet::new(2, vec![4], 3);
+		let input = vec![0.3, 0.9];
+		let output = nn.feed_forward(input.clone()).unwrap();
+		let expected = (0..3).fold(0, |best, i| if output[i] > output[best] { i } else { best });
+		assert_eq!(nn.predict_label(input).unwrap(), expected as u32);
+
+		// all outputs are equal, so the first one wins
+		let mut tied = NeuralNet::new(2, Vec::new(), 3);
+		tied.hidden_weights[0] = Matrix::new(3, 2);
+		tied.set_bias(0.);
+		assert_eq!(tied.predict_label(vec![0.3, 0.9]).unwrap(), 0);
+	}
+
+	#[test]
+	fn top_k_scored()
+	{
+		// with identity weights the logits are the inputs plus the bias
+		let mut nn = NeuralNet::new(3, Vec::new(), 3);
+		nn.hidden_weights[0] = Matrix::from(3, 3, vec![1., 0., 0., 0., 1., 0., 0., 0., 1.]).unwrap();
+		nn.set_bias(1.);
+		let logits = [2_f64, 4., 3.];
+		let sum: f64 = logits.iter().map(|l| l.exp()).sum();
+		let p = |i: usize| logits[i].exp() / sum;
+
+		let top = nn.top_k_scored(vec![1., 3., 2.], 2).unwrap();
+		assert_eq!(top.len(), 4);
+		assert_eq!(top[0], 1.);
+		assert!((top[1] - p(1)).abs() < 1e-12);
+		assert_eq!(top[2], 2.);
+		assert!((top[3] - p(2)).abs() < 1e-12);
+
+		let all = nn.top_k_scored(vec![1., 3., 2.], 10).unwrap();
+		assert_eq!(all.len(), 6);
+		assert_eq!(all[4], 0.);
+	}
+
+	#[test]
+	fn fgsm()
+	{
+		let nn = NeuralNet::new(3, vec![4], 2);
+		let input = vec![0.5, 0.2, 0.9];
+		let adversarial = nn.fgsm(input.clone(), 1, 0.05).unwrap();
+
+		assert!(adversarial.iter().zip(input.iter()).all(|(a, i)| (a - i).abs() <= 0.05 + 1e-12));
+		assert!(nn.feed_forward(adversarial).unwrap()[1] > nn.feed_forward(input.clone()).unwrap()[1]);
+
+		// large steps are clamped to [0, 1]
+		let clamped = nn.fgsm(input, 0, 5.).unwrap();
+		assert!(clamped.iter().all(|v| *v == 0. || *v == 1.));
+	}
+
+	#[test]
+	fn json_round_trip()
+	{
+		let mut nn = NeuralNet::new(3, vec![4, 2], 2);
+		nn.learning_rate = 0.25;
+		let loaded = NeuralNet::from_json(&nn.to_json()).unwrap();
+
+		assert_eq!(loaded.hidden_nodes, nn.hidden_nodes);
+		assert_eq!(loaded.learning_rate, nn.learning_rate);
+		assert_eq!(loaded.hidden_weights.len(), nn.hidden_weights.len());
+		assert_eq!(loaded.biases.len(), nn.biases.len());
+		for (a, b) in loaded.hidden_weights.iter().zip(nn.hidden_weights.iter()).chain(loaded.biases.iter().zip(nn.biases.iter())) {
+			assert_eq!(a.rows(), b.rows());
+			assert_eq!(a.cols(), b.cols());
+			assert_eq!(a.data(), b.data());
+		}
+	}
+
+	#[test]
+	fn buffer_round_trip()
+	{
+		let mut nn = NeuralNet::new(3, vec![4, 2], 2);
+		nn.set_activation(Activation::Tanh);
+		nn.set_bias(0.25);
+		nn.learning_rate = 0.05;
+
+		let buffer = nn.export_buffer();
+		// 20 header bytes + 3 shapes, padded to 48, then 24 weights and 8 biases
+		assert_eq!(buffer.len(), 48 + 32 * 8);
+		assert_eq!(&buffer[48..56], &nn.hidden_weights[0].get(0, 0).to_le_bytes());
+		assert_eq!(&buffer[48 + 24 * 8..48 + 25 * 8], &0.25_f64.to_le_bytes());
+
+		let loaded = NeuralNet::import_buffer(&buffer).unwrap();
+		assert_eq!(loaded.hidden_nodes, nn.hidden_nodes);
+		assert_eq!(loaded.activation, Activation::Tanh);
+		assert_eq!(loaded.learning_rate(), 0.05);
+		for (a, b) in loaded.hidden_weights.iter().zip(nn.hidden_weights.iter()) {
+			assert_eq!(a.data(), b.data());
+		}
+		for (a, b) in loaded.biases.iter().zip(nn.biases.iter()) {
+			assert_eq!(a.data(), b.data());
+		}
+	}
+
+	#[test]
+	fn memory_bytes()
+	{
+		// 3x2 + 1x3 weights and 3 + 1 biases = 13 parameters
+		let nn = NeuralNet::new(2, vec![3], 1);
+		let expected = size_of::<NeuralNet>()
+			+ 4 * size_of::<Matrix>()
+			+ size_of::<u32>()
+			+ 13 * 8;
+		assert_eq!(nn.memory_bytes(), expected);
+	}
+
+	#[test]
+	fn snapshot_restore()
+	{
+		let mut nn = NeuralNet::new(2, vec![2], 1);
+		nn.hidden_weights[0] = Matrix::from(2, 2, vec![0.1, 0.2, 0.3, 0.4]).unwrap();
+		nn.hidden_weights[1] = Matrix::from(1, 2, vec![0.5, 0.6]).unwrap();
+		let biases = nn.biases[0].data();
+		let snap = nn.snapshot();
+
+		nn.hidden_weights[0].scale(5.);
+		nn.hidden_weights[1].map(|_, _, _| 1.);
+		nn.set_bias(0.);
+
+		nn.restore(&snap).unwrap();
+		assert_eq!(nn.hidden_weights[0].data(), vec![0.1, 0.2, 0.3, 0.4]);
+		assert_eq!(nn.hidden_weights[1].data(), vec![0.5, 0.6]);
+		assert_eq!(nn.biases[0].data(), biases);
+	}
+
+	#[test]
+	fn average()
+	{
+		let mut a = NeuralNet::new(2, vec![2], 1);
+		let mut b = NeuralNet::new(2, vec![2], 1);
+		a.hidden_weights[0] = Matrix::from(2, 2, vec![1., 2., 3., 4.]).unwrap();
+		b.hidden_weights[0] = Matrix::from(2, 2, vec![3., 2., 1., 0.]).unwrap();
+		a.hidden_weights[1] = Matrix::from(1, 2, vec![-1., 5.]).unwrap();
+		b.hidden_weights[1] = Matrix::from(1, 2, vec![1., 1.]).unwrap();
+
+		let avg = NeuralNet::average(vec![a, b]).unwrap();
+		assert_eq!(avg.hidden_nodes, vec![2]);
+		assert_eq!(avg.hidden_weights[0].data(), vec![2., 2., 2., 2.]);
+		assert_eq!(avg.hidden_weights[1].data(), vec![0., 3.]);
+	}
+
+	#[test]
+	fn k_fold_indices()
+	{
+		let folds = NeuralNet::k_fold_indices(23, 5, 42);
+		assert_eq!(folds, NeuralNet::k_fold_indices(23, 5, 42));
+
+		let mut sizes = [0; 5];
+		for fold in folds.iter() {
+			sizes[*fold as usize] += 1;
+		}
+		let largest = *sizes.iter().max().unwrap();
+		let smallest = *sizes.iter().min().unwrap();
+		assert!(largest - smallest <= 1);
+		assert_eq!(sizes.iter().sum::<u32>(), 23);
+	}
+
+	#[test]
+	fn soft_reset()
+	{
+		let mut nn = NeuralNet::new(2, vec![2], 1);
+		nn.hidden_weights[0] = Matrix::from(2, 2, vec![0.1, 0.2, 0.3, 0.4]).unwrap();
+		let output_weights = nn.hidden_weights[1].data();
+		nn.soft_reset(0., 5);
+		assert_eq!(nn.hidden_weights[0].data(), vec![0.1, 0.2, 0.3, 0.4]);
+		assert_eq!(nn.hidden_weights[1].data(), output_weights);
+
+		// a full reset no longer depends on the previous weights
+		let mut other = NeuralNet::new(2, vec![2], 1);
+		nn.soft_reset(1., 9);
+		other.soft_reset(1., 9);
+		assert_eq!(nn.hidden_weights[0].data(), other.hidden_weights[0].data());
+		assert_eq!(nn.hidden_weights[1].data(), other.hidden_weights[1].data());
+		assert_ne!(nn.hidden_weights[0].data(), vec![0.1, 0.2, 0.3, 0.4]);
+	}
+
+	#[test]
+	fn reshape_topology()
+	{
+		let nn = NeuralNet::new(3, vec![4, 2], 2);
+		let same = nn.reshape_topology(vec![4, 2], 1);
+		assert_eq!(same.shape(), nn.shape());
+		for i in 0..nn.hidden_weights.len() {
+			assert_eq!(same.hidden_weights[i].data(), nn.hidden_weights[i].data());
+			assert_eq!(same.biases[i].data(), nn.biases[i].data());
+		}
+
+		// only the first layer still has matching dimensions
+		let other = nn.reshape_topology(vec![4, 5], 1);
+		assert!(other.validate().is_ok());
+		assert_eq!(other.hidden_weights[0].data(), nn.hidden_weights[0].data());
+		assert_eq!(other.hidden_weights[1].rows(), 5);
+		assert_eq!(other.hidden_weights[2].cols(), 5);
+		assert_eq!(other.hidden_weights[2].data(), nn.reshape_topology(vec![4, 5], 1).hidden_weights[2].data());
+	}
+
+	#[test]
+	fn shape()
+	{
+		assert_eq!(NeuralNet::new(3, vec![2, 4], 2).shape(), vec![3, 2, 4, 2]);
+		assert_eq!(NeuralNet::new(5, Vec::new(), 1).shape(), vec![5, 1]);
+
+		let nn = NeuralNet::new(4, vec![3], 2);
+		assert_eq!(NeuralNet::from_json(&nn.to_json()).unwrap().shape(), vec![4, 3, 2]);
+		assert_eq!(NeuralNet::import_buffer(&nn.export_buffer()).unwrap().shape(), vec![4, 3, 2]);
+	}
+
+	#[test]
+	fn validate()
+	{
+		assert!(NeuralNet::new(3, vec![2, 4], 2).validate().is_ok());
+		assert!(NeuralNet::new(3, Vec::new(), 2).validate().is_ok());
+	}
+
+	// Building a `JsValue` error aborts on native targets, so the failure
+	// paths can only be exercised with `wasm-pack test`.
+	#[cfg(target_arch = "wasm32")]
+	mod wasm
+	{
+		use super::super::NeuralNet;
+		use crate::Matrix;
+		use wasm_bindgen_test::wasm_bindgen_test;
+
+		#[wasm_bindgen_test]
+		fn feed_forward_wrong_length()
+		{
+			let nn = NeuralNet::new(2, vec![3], 1);
+			assert!(nn.feed_forward(vec![0., 1., 2.]).is_err());
+			assert!(nn.feed_forward(Vec::new()).is_err());
+			assert!(nn.predict_label(vec![0.]).is_err());
+			assert!(nn.feed_forward_softmax(vec![0., 1., 2.]).is_err());
+			assert!(nn.feed_forward_softmax(Vec::new()).is_err());
+			assert!(nn.top_k_scored(vec![0.], 1).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn train_wrong_lengths()
+		{
+			let mut nn = NeuralNet::new(2, vec![3], 1);
+			assert!(nn.train(vec![0., 1., 2.], vec![1.]).is_err());
+			assert!(nn.train(vec![0., 1.], vec![1., 0.]).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn fgsm_invalid_arguments()
+		{
+			let nn = NeuralNet::new(2, vec![3], 2);
+			assert!(nn.fgsm(vec![0.5], 0, 0.1).is_err());
+			assert!(nn.fgsm(vec![0.5, 0.5], 2, 0.1).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn weights_invalid()
+		{
+			let mut nn = NeuralNet::new(2, vec![3], 1);
+			let before = nn.weights(0).unwrap().data();
+			assert!(nn.weights(2).is_err());
+			assert!(nn.set_weights(2, Matrix::new(1, 3)).is_err());
+			assert!(nn.set_weights(0, Matrix::new(2, 3)).is_err());
+			assert_eq!(nn.weights(0).unwrap().data(), before);
+		}
+
+		#[wasm_bindgen_test]
+		fn set_learning_rate_invalid()
+		{
+			let mut nn = NeuralNet::new(2, vec![3], 1);
+			assert!(nn.set_learning_rate(-0.1).is_err());
+			assert!(nn.set_learning_rate(0.).is_err());
+			assert!(nn.set_learning_rate(f64::NAN).is_err());
+			assert_eq!(nn.learning_rate(), 0.1);
+		}
+
+		#[wasm_bindgen_test]
+		fn fit_wrong_lengths()
+		{
+			let mut nn = NeuralNet::new(2, vec![3], 1);
+			assert!(nn.fit(vec![0., 1., 1.], vec![1., 0.], 2, 1).is_err());
+			assert!(nn.fit(vec![0., 1., 1., 0.], vec![1.], 2, 1).is_err());
+			assert!(nn.fit(Vec::new(), Vec::new(), 0, 1).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn per_class_accuracy_invalid()
+		{
+			let nn = NeuralNet::new(2, vec![3], 2);
+			assert!(nn.per_class_accuracy(vec![0.5, 0.1, 0.2], vec![0, 1], 2, 2).is_err());
+			assert!(nn.per_class_accuracy(vec![0.5, 0.1, 0.2, 0.9], vec![0], 2, 2).is_err());
+			assert!(nn.per_class_accuracy(vec![0.5, 0.1, 0.2, 0.9], vec![0, 2], 2, 2).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn train_split_invalid_fraction()
+		{
+			let mut nn = NeuralNet::new(2, vec![3], 1);
+			for fraction in [-0.1, 1., f64::NAN].iter() {
+				assert!(nn.train_split(vec![0., 1., 1., 0.], vec![1., 1.], 2, 3, *fraction, 1).is_err());
+			}
+		}
+
+		#[wasm_bindgen_test]
+		fn mse_wrong_lengths()
+		{
+			let nn = NeuralNet::new(2, vec![3], 1);
+			assert!(nn.mse(vec![0., 1.], vec![1., 0.]).is_err());
+			assert!(nn.mse(vec![0.], vec![1.]).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn set_output_group_weights_invalid()
+		{
+			let mut nn = NeuralNet::new(2, vec![3], 2);
+			assert!(nn.set_output_group_weights(vec![0], vec![1.]).is_err());
+			assert!(nn.set_output_group_weights(vec![0, 2], vec![1., 1.]).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn from_json_invalid()
+		{
+			assert!(NeuralNet::from_json("not json").is_err());
+
+			let mut nn = NeuralNet::new(2, vec![3], 1);
+			nn.hidden_weights[1] = Matrix::new(1, 2);
+			assert!(NeuralNet::from_json(&nn.to_json()).is_err());
+
+			let mut without_biases: serde_json::Value = serde_json::from_str(&NeuralNet::new(2, vec![3], 1).to_json()).unwrap();
+			without_biases.as_object_mut().unwrap().remove("biases");
+			assert!(NeuralNet::from_json(&without_biases.to_string()).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn import_buffer_malformed()
+		{
+			let buffer = NeuralNet::new(2, vec![3], 1).export_buffer();
+			assert!(NeuralNet::import_buffer(&[]).is_err());
+			assert!(NeuralNet::import_buffer(&buffer[..buffer.len() - 1]).is_err());
+
+			let mut trailing = buffer.clone();
+			trailing.push(0);
+			assert!(NeuralNet::import_buffer(&trailing).is_err());
+
+			let mut wrong_magic = buffer.clone();
+			wrong_magic[0] = b'X';
+			assert!(NeuralNet::import_buffer(&wrong_magic).is_err());
+
+			// reshape the first layer from 3x2 to 6x1, which breaks the chain
+			let mut broken_chain = buffer;
+			broken_chain[20] = 6;
+			broken_chain[24] = 1;
+			assert!(NeuralNet::import_buffer(&broken_chain).is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn validate_broken_chain()
+		{
+			let mut nn = NeuralNet::new(3, vec![2, 4], 2);
+			nn.hidden_weights[1] = Matrix::new(4, 3);
+			assert!(nn.validate().is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn validate_hidden_nodes_mismatch()
+		{
+			let mut nn = NeuralNet::new(3, vec![2, 4], 2);
+			nn.hidden_nodes[1] = 5;
+			assert!(nn.validate().is_err());
+		}
+
+		#[wasm_bindgen_test]
+		fn validate_missing_layer()
+		{
+			let mut nn = NeuralNet::new(3, vec![2, 4], 2);
+			nn.hidden_weights.pop();
+			assert!(nn.validate().is_err());
+		}
 	}
 }